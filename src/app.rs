@@ -4,25 +4,58 @@ use eframe::App;
 use tokio::sync::mpsc;
 use tracing::error; 
 
-use egui_plot::{Line, Plot, Legend}; 
 
 use crate::data_model::{MarketDataUpdate, Order, OrderType, Side, TimeInForce};
-use crate::oms::OmsUpdate;
+use crate::config::{BarSpec, Venue};
+use crate::market_data::candles::CandleUpdate;
+use crate::market_data::{ConnectionStatus, MarketDataCommand, OrderBooks, VenueStatus};
+use crate::market_data::replay::{ReplayCommand, ReplayHandle, ReplaySpeed, ReplayStatus};
+use crate::control::{self, ControlHandle};
+use crate::oms::{Fill, KillSwitchEvent, OmsCommand, OmsUpdate};
+use crate::oms::position::Position;
+use std::collections::{BTreeMap, HashMap};
+use chrono::Utc;
 use crate::strategy::StrategyControl;
 
+mod chart;
+mod ladder;
+mod market_state;
+mod pnl;
+mod tape;
+
+use chart::{BarSeries, CandleChart};
+use ladder::LadderAction;
+use market_state::MarketState;
+use pnl::PnlHistory;
+use tape::TradeTape;
+
 pub struct AlgoApp {
     
     market_data_rx: mpsc::UnboundedReceiver<MarketDataUpdate>,
+    candle_rx: mpsc::UnboundedReceiver<CandleUpdate>,
+    md_status_rx: mpsc::UnboundedReceiver<VenueStatus>,
     oms_rx: mpsc::UnboundedReceiver<OmsUpdate>,
 
     
-    order_tx: mpsc::UnboundedSender<Order>,
+    order_tx: mpsc::UnboundedSender<OmsCommand>,
     strategy_tx: mpsc::UnboundedSender<StrategyControl>,
+    md_command_tx: mpsc::UnboundedSender<MarketDataCommand>,
+    control: ControlHandle,
+    order_books: OrderBooks,
+    replay: Option<ReplayHandle>,
 
     
-    last_market_data: Option<MarketDataUpdate>,
+    market: MarketState,
+    tape: TradeTape,
+    /// Drives the chart, the order book and order entry.
+    selected_symbol: Option<String>,
+    md_status: BTreeMap<Venue, ConnectionStatus>,
+    symbols: Vec<String>,
+    input_new_symbol: String,
     orders: Vec<Order>,
-    oms_log: Vec<String>, 
+    positions: HashMap<String, Position>,
+    pnl_history: PnlHistory,
+    rejection_reasons: HashMap<uuid::Uuid, String>,
     app_log: Vec<String>,
 
     input_symbol: String,
@@ -34,48 +67,130 @@ pub struct AlgoApp {
 
     strategy_status: String,
 
-    price_history: Vec<[f64; 2]>,
+    candles: HashMap<(String, BarSpec), BarSeries>,
+    /// Bar types in the order they were first seen.
+    bar_specs: Vec<BarSpec>,
+    chart: CandleChart,
+    /// Our recent fills, for the chart markers.
+    fills: Vec<Fill>,
+
+    amend: Option<AmendDraft>,
+    kill_switch: Option<KillSwitchEvent>,
+
+    replay_status: Option<ReplayStatus>,
+    /// Seek slider position (seconds from the start) while it is dragged.
+    replay_seek: Option<f64>,
+}
+
+/// In-progress edit of a working order from the orders table.
+struct AmendDraft {
+    order_id: uuid::Uuid,
+    price: String,
+    quantity: String,
+}
+
+enum OrderAction {
+    Cancel(uuid::Uuid),
+    StartAmend(uuid::Uuid),
+}
+
+/// Channel ends the UI reads from and writes to.
+pub struct AppChannels {
+    pub market_data_rx: mpsc::UnboundedReceiver<MarketDataUpdate>,
+    pub candle_rx: mpsc::UnboundedReceiver<CandleUpdate>,
+    pub md_status_rx: mpsc::UnboundedReceiver<VenueStatus>,
+    pub oms_rx: mpsc::UnboundedReceiver<OmsUpdate>,
+    pub order_tx: mpsc::UnboundedSender<OmsCommand>,
+    pub strategy_tx: mpsc::UnboundedSender<StrategyControl>,
+    pub md_command_tx: mpsc::UnboundedSender<MarketDataCommand>,
+    /// Set when running on recorded data instead of the live feeds.
+    pub replay: Option<ReplayHandle>,
 }
 
 impl AlgoApp {
     pub fn new(
-        market_data_rx: mpsc::UnboundedReceiver<MarketDataUpdate>,
-        oms_rx: mpsc::UnboundedReceiver<OmsUpdate>,
-        order_tx: mpsc::UnboundedSender<Order>,
-        strategy_tx: mpsc::UnboundedSender<StrategyControl>,
+        channels: AppChannels,
+        control: ControlHandle,
+        order_books: OrderBooks,
+        symbols: Vec<String>,
+        strategy_enabled: bool,
     ) -> Self {
         Self {
-            market_data_rx,
-            oms_rx,
-            order_tx,
-            strategy_tx,
-            last_market_data: None,
+            market_data_rx: channels.market_data_rx,
+            candle_rx: channels.candle_rx,
+            md_status_rx: channels.md_status_rx,
+            oms_rx: channels.oms_rx,
+            order_tx: channels.order_tx,
+            strategy_tx: channels.strategy_tx,
+            md_command_tx: channels.md_command_tx,
+            control,
+            order_books,
+            replay: channels.replay,
+            market: MarketState::default(),
+            tape: TradeTape::default(),
+            selected_symbol: symbols.first().map(|s| s.to_uppercase()),
+            md_status: BTreeMap::new(),
+            symbols: symbols.iter().map(|s| s.to_uppercase()).collect(),
+            input_new_symbol: String::new(),
             orders: Vec::new(),
-            oms_log: Vec::new(),
+            positions: HashMap::new(),
+            pnl_history: PnlHistory::default(),
+            rejection_reasons: HashMap::new(),
             app_log: Vec::new(),
-            input_symbol: "BTCUSD".to_string(),
+            input_symbol: symbols.first().map(|s| s.to_uppercase()).unwrap_or_default(),
             input_price: "0.0".to_string(),
             input_quantity: "1.0".to_string(),
             input_side: Side::Buy,
             input_order_type: OrderType::Limit,
             input_tif: TimeInForce::GTC,
-            strategy_status: "Inactive".to_string(),
-            price_history: Vec::new(),
+            strategy_status: if strategy_enabled { "Running" } else { "Stopped" }.to_string(),
+            candles: HashMap::new(),
+            bar_specs: Vec::new(),
+            chart: CandleChart::default(),
+            fills: Vec::new(),
+            amend: None,
+            kill_switch: None,
+            replay_status: None,
+            replay_seek: None,
         }
     }
 
     fn handle_market_data_updates(&mut self) {
         while let Ok(update) = self.market_data_rx.try_recv() {
-            self.last_market_data = Some(update.clone());
-
-            if let Some(last_price) = update.last_price {
-                self.price_history.push([
-                    update.timestamp.timestamp_millis() as f64,
-                    last_price,
-                ]);
-                if self.price_history.len() > 1000 { 
-                    self.price_history.remove(0);
-                }
+            self.market.apply(&update);
+            self.tape.apply(&update);
+        }
+    }
+
+    fn handle_candle_updates(&mut self) {
+        const MAX_BARS: usize = 500;
+        while let Ok(CandleUpdate { symbol, spec, candle, closed }) = self.candle_rx.try_recv() {
+            if !self.bar_specs.contains(&spec) {
+                self.bar_specs.push(spec);
+            }
+            let bars = self.candles.entry((symbol, spec)).or_default();
+            bars.insert(candle.timestamp, (candle, closed));
+            if bars.len() > MAX_BARS {
+                bars.pop_first();
+            }
+        }
+    }
+
+    fn handle_connection_status(&mut self) {
+        while let Ok(VenueStatus { venue, status }) = self.md_status_rx.try_recv() {
+            if self.md_status.get(&venue) != Some(&status) {
+                self.log_message(format!("Market data ({}): {}", venue, status));
+            }
+            self.md_status.insert(venue, status);
+        }
+    }
+
+    fn handle_replay_status(&mut self) {
+        while let Some(Ok(status)) = self.replay.as_mut().map(|r| r.status_rx.try_recv()) {
+            let finished = status.finished() && !self.replay_status.as_ref().is_some_and(ReplayStatus::finished);
+            self.replay_status = Some(status);
+            if finished {
+                self.log_message("Replay finished.".to_string());
             }
         }
     }
@@ -87,14 +202,66 @@ impl AlgoApp {
                     self.log_message(format!("Order Created: {:?} {}", order.side, order.symbol));
                     self.orders.push(order); 
                 }
-                OmsUpdate::OrderStateChange { order_id, new_state, .. } => {
+                OmsUpdate::OrderStateChange { order_id, new_state, timestamp } => {
                     if let Some(order) = self.orders.iter_mut().find(|o| o.order_id == order_id) {
                         order.state = new_state.clone();
-                        self.log_message(format!("Order {} State Changed: {:?}", order_id, new_state));
+                        self.log_message(format!("Order {} State Changed: {:?} at {}", order_id, new_state, timestamp.format("%H:%M:%S%.3f")));
+                    }
+                }
+                OmsUpdate::OrderReplaced { order_id, price, quantity, timestamp } => {
+                    if let Some(order) = self.orders.iter_mut().find(|o| o.order_id == order_id) {
+                        order.price = price;
+                        order.quantity = quantity;
+                        self.log_message(format!("Order {} amended: {} @ {:?} at {}", order_id, quantity, price, timestamp.format("%H:%M:%S%.3f")));
+                    }
+                }
+                OmsUpdate::OrderRejected { order_id, reason } => {
+                    self.log_message(format!("Order {} rejected: {}", order_id, reason));
+                    self.rejection_reasons.insert(order_id, reason);
+                }
+                OmsUpdate::RequestRejected { order_id, reason } => {
+                    self.log_message(format!("Request for order {} rejected: {}", order_id, reason));
+                }
+                OmsUpdate::KillSwitch(event) => {
+                    if event.engaged {
+                        self.log_message(format!(
+                            "KILL SWITCH engaged by {} ({}), canceled {} orders",
+                            event.triggered_by,
+                            event.reason.as_deref().unwrap_or_default(),
+                            event.canceled_orders.len()
+                        ));
+                        self.strategy_status = "Stopped (kill switch)".to_string();
+                        self.kill_switch = Some(event);
+                    } else {
+                        self.log_message(format!("Kill switch re-armed by {}", event.triggered_by));
+                        self.kill_switch = None;
+                        // Re-arming only allows orders again; strategies stay stopped.
+                        self.strategy_status = "Stopped (start manually)".to_string();
+                    }
+                }
+                OmsUpdate::Fill(fill) => {
+                    self.log_message(format!(
+                        "Fill {}: {:?} {} {} @ {:.2} at {}",
+                        fill.order_id.simple(), fill.side, fill.quantity, fill.symbol, fill.price,
+                        fill.timestamp.format("%H:%M:%S%.3f")
+                    ));
+                    self.fills.push(fill);
+                    if self.fills.len() > 1000 {
+                        self.fills.remove(0);
                     }
                 }
                 OmsUpdate::PositionUpdate(pos) => {
-                    self.log_message(format!("Position Update: {} {} @ {:.2}", pos.symbol, pos.quantity, pos.avg_cost));
+                    let quantity_changed = self.positions
+                        .get(&pos.symbol)
+                        .is_none_or(|prev| prev.quantity != pos.quantity);
+                    if quantity_changed {
+                        self.log_message(format!(
+                            "Position Update: {} {} @ {:.2} (realized {:.2}, unrealized {:.2})",
+                            pos.symbol, pos.quantity, pos.avg_cost, pos.pnl_realized, pos.pnl_unrealized
+                        ));
+                    }
+                    self.positions.insert(pos.symbol.clone(), pos);
+                    self.pnl_history.record(Utc::now(), &self.positions);
                 }
             }
         }
@@ -108,52 +275,188 @@ impl AlgoApp {
     }
 
 
-    fn render_market_data_panel(&mut self, ui: &mut egui::Ui) {
+    fn send_market_data_command(&mut self, command: MarketDataCommand) {
+        if command.apply(&mut self.symbols).is_none() {
+            return;
+        }
+        self.log_message(format!("Market data: {:?}", command));
+        if let Err(e) = self.md_command_tx.send(command) {
+            error!("Failed to send market data command: {}", e);
+            self.log_message(format!("Error: {}", e));
+        }
+    }
+
+    fn select_symbol(&mut self, symbol: String) {
+        self.input_symbol = symbol.clone();
+        self.selected_symbol = Some(symbol);
+    }
+
+    fn render_symbol_selector(&mut self, ui: &mut egui::Ui) {
+        let mut removed = None;
+        let mut selected = None;
+        ui.horizontal_wrapped(|ui| {
+            ui.label("Symbols:");
+            for symbol in &self.symbols {
+                let is_selected = self.selected_symbol.as_ref() == Some(symbol);
+                if ui.selectable_label(is_selected, symbol).clicked() {
+                    selected = Some(symbol.clone());
+                }
+                if ui.small_button("✖").on_hover_text(format!("Unsubscribe {}", symbol)).clicked() {
+                    removed = Some(symbol.clone());
+                }
+            }
+            ui.add(egui::TextEdit::singleline(&mut self.input_new_symbol).desired_width(80.0).hint_text("e.g. SOLUSDT"));
+            if ui.button("Add").clicked() && !self.input_new_symbol.trim().is_empty() {
+                let symbol = std::mem::take(&mut self.input_new_symbol);
+                self.send_market_data_command(MarketDataCommand::Subscribe(symbol));
+            }
+        });
+        if self.selected_symbol.is_none() {
+            selected = selected.or_else(|| self.symbols.first().cloned());
+        }
+        if let Some(symbol) = selected {
+            self.select_symbol(symbol);
+        }
+        if let Some(symbol) = removed {
+            self.send_market_data_command(MarketDataCommand::Unsubscribe(symbol.clone()));
+            if self.selected_symbol.as_ref() == Some(&symbol) {
+                self.selected_symbol = self.symbols.first().cloned();
+            }
+        }
+    }
+
+    fn render_watchlist(&mut self, ui: &mut egui::Ui) {
+        let mut selected = None;
         ui.group(|ui| {
-            ui.heading("Market Data & Chart");
+            ui.heading("Watchlist");
+            egui::Grid::new("watchlist_grid").striped(true).num_columns(7).show(ui, |ui| {
+                for header in ["Symbol", "Last", "Bid", "Ask", "Spread", "24h Chg", "24h Vol"] {
+                    ui.label(RichText::new(header).strong());
+                }
+                ui.end_row();
 
-    
-            if let Some(md) = &self.last_market_data {
-                ui.label(format!("Symbol: {}", md.symbol));
-                if let Some(last_price) = md.last_price {
-                    ui.label(format!("Last Price: {:.2}", last_price));
+                let price = |p: Option<f64>| p.map(|p| format!("{:.2}", p)).unwrap_or_else(|| "-".to_string());
+                for symbol in &self.symbols {
+                    let is_selected = self.selected_symbol.as_ref() == Some(symbol);
+                    if ui.selectable_label(is_selected, symbol).clicked() {
+                        selected = Some(symbol.clone());
+                    }
+                    let Some(state) = self.market.get(symbol) else {
+                        ui.label("waiting...");
+                        ui.end_row();
+                        continue;
+                    };
+                    ui.label(price(state.last_price));
+                    ui.label(RichText::new(price(state.bid.map(|b| b.0))).color(Color32::LIGHT_GREEN));
+                    ui.label(RichText::new(price(state.ask.map(|a| a.0))).color(Color32::LIGHT_RED));
+                    ui.label(price(state.spread()));
+                    match state.change_24h() {
+                        Some(change) => {
+                            let color = if change >= 0.0 { Color32::LIGHT_GREEN } else { Color32::LIGHT_RED };
+                            ui.label(RichText::new(format!("{:+.2}%", change * 100.0)).color(color))
+                        }
+                        None => ui.label("-"),
+                    };
+                    ui.label(format!("{:.4}", state.volume_24h()));
+                    ui.end_row();
                 }
-                ui.label(format!("Time: {}", md.timestamp.format("%H:%M:%S")));
+            });
+        });
+        if let Some(symbol) = selected {
+            self.select_symbol(symbol);
+        }
+    }
+
+    fn render_market_data_panel(&mut self, ui: &mut egui::Ui) {
+        ui.group(|ui| {
+            ui.heading("Market Data & Chart");
+            self.render_symbol_selector(ui);
+
+            let state = self.selected_symbol.as_ref().and_then(|s| Some((s, self.market.get(s)?)));
+            if let Some((symbol, state)) = state {
+                ui.horizontal(|ui| {
+                    ui.label(RichText::new(symbol).strong());
+                    if let Some(last_price) = state.last_price {
+                        ui.label(format!("Last {:.2}", last_price));
+                    }
+                    if let (Some((bid, _)), Some((ask, _))) = (state.bid, state.ask) {
+                        ui.label(format!("Bid {:.2} / Ask {:.2}", bid, ask));
+                    }
+                    if let Some(updated_at) = state.updated_at {
+                        ui.label(format!("at {}", updated_at.format("%H:%M:%S")));
+                    }
+                });
             } else {
                 ui.label("Waiting for market data...");
             }
 
             ui.add_space(10.0);
+            self.chart.show(ui, self.selected_symbol.as_deref(), &self.bar_specs, &self.candles, &self.fills);
+        });
+    }
 
-            
-            if !self.price_history.is_empty() {
-                Plot::new("price_chart")
-                    .height(300.0) 
-                    .data_aspect(1.0) 
-                    .show_axes([true, true])
-                    .show_x(true)
-                    .show_y(true)
-                    .label_formatter(|name, value| { 
-                        if name == "X" {
-                            let timestamp_ms = value.x as i64;
-                            let datetime = chrono::DateTime::from_timestamp_millis(timestamp_ms)
-                                .unwrap_or_else(|| chrono::Utc::now()); 
-                            format!("{}", datetime.format("%H:%M:%S"))
-                        } else {
-                            format!("{}: {:.2}", name, value.y)
-                        }
-                    })
-                    .legend(Legend::default()) 
-                    .show(ui, |plot_ui| {
-                        let line = Line::new(self.price_history.clone())
-                            .name("Last Price")
-                            .color(Color32::LIGHT_GREEN);
-                        plot_ui.line(line);
-                    });
-            } else {
-                ui.label("No price data for chart yet.");
-            }
+    fn render_order_book_panel(&mut self, ui: &mut egui::Ui) {
+        const DEPTH: usize = 10;
+        let mut action = None;
+        ui.group(|ui| {
+            ui.heading("Order Book");
+
+            let Some(symbol) = self.selected_symbol.clone() else {
+                ui.label("No symbol selected.");
+                return;
+            };
+            let book = self.order_books.read(&symbol, |book| {
+                (book.is_synced(), book.top_bids(DEPTH), book.top_asks(DEPTH), book.mid_price())
+            });
+            let (bids, asks, mid) = match book {
+                Some((true, bids, asks, mid)) => (bids, asks, mid),
+                Some((false, ..)) => {
+                    ui.label(format!("{}: synchronizing...", symbol));
+                    return;
+                }
+                None => {
+                    ui.label(format!("No depth feed for {}", symbol));
+                    return;
+                }
+            };
+
+            match mid {
+                Some(mid) => ui.label(format!("{}  mid {:.2}", symbol, mid)),
+                None => ui.label(symbol.clone()),
+            };
+            let trading = self.kill_switch.is_none();
+            ui.label(RichText::new(format!(
+                "Click a bid/ask size to place a {} {:?} limit; click our size to cancel.",
+                self.input_quantity, self.input_tif
+            )).weak());
+            let orders: Vec<Order> = self.orders.iter().filter(|o| o.symbol == symbol).cloned().collect();
+            action = ladder::show(ui, &bids, &asks, &orders, trading);
         });
+
+        match action {
+            Some(LadderAction::Place { side, price }) => self.place_ladder_order(side, price),
+            Some(LadderAction::Cancel(order_ids)) => {
+                for order_id in order_ids {
+                    self.log_message(format!("Cancel requested for order {} from the ladder", order_id));
+                    self.send_command(OmsCommand::Cancel { order_id });
+                }
+            }
+            None => {}
+        }
+    }
+
+    fn place_ladder_order(&mut self, side: Side, price: f64) {
+        let Some(symbol) = self.selected_symbol.clone() else {
+            return;
+        };
+        let quantity = self.input_quantity.parse::<f64>().unwrap_or_default();
+        if quantity <= 0.0 {
+            self.log_message("Invalid ladder order: Quantity must be > 0.".to_string());
+            return;
+        }
+        self.log_message(format!("Ladder order: {:?} {} {} @ {:.2}", side, quantity, symbol, price));
+        let order = Order::new(symbol, side, OrderType::Limit, quantity, Some(price), self.input_tif.clone());
+        self.send_command(OmsCommand::New(order));
     }
 
     fn render_order_entry_panel(&mut self, ui: &mut egui::Ui) {
@@ -192,7 +495,11 @@ impl AlgoApp {
             });
             ui.add_space(10.0);
 
-            if ui.button(RichText::new("Place Order").strong().color(Color32::WHITE)).clicked() {
+            let halted = self.kill_switch.is_some();
+            if halted {
+                ui.label(RichText::new("Order entry blocked: kill switch engaged").color(Color32::LIGHT_RED));
+            }
+            if ui.add_enabled(!halted, egui::Button::new(RichText::new("Place Order").strong().color(Color32::WHITE))).clicked() {
                 let quantity = self.input_quantity.parse::<f64>().unwrap_or_default();
                 let price = self.input_price.parse::<f64>().ok();
 
@@ -205,7 +512,7 @@ impl AlgoApp {
                         price,
                         self.input_tif.clone(),
                     );
-                    if let Err(e) = self.order_tx.send(new_order) {
+                    if let Err(e) = self.order_tx.send(OmsCommand::New(new_order)) {
                         error!("Failed to send order to OMS: {}", e); 
                         self.log_message(format!("Failed to place order: {}", e));
                     } else {
@@ -218,12 +525,37 @@ impl AlgoApp {
         });
     }
 
+    fn trigger_kill_switch(&mut self, channel: &str) {
+        let operator = control::operator(channel);
+        self.log_message(format!("Kill switch triggered by {}", operator));
+        if let Err(e) = self.control.engage_kill_switch(&operator, "manual kill switch from UI") {
+            error!("Failed to engage kill switch: {}", e);
+            self.log_message(format!("Failed to engage kill switch: {}", e));
+        }
+    }
+
+    fn rearm_kill_switch(&mut self) {
+        let operator = control::operator("ui");
+        if let Err(e) = self.control.rearm(&operator) {
+            error!("Failed to re-arm kill switch: {}", e);
+            self.log_message(format!("Failed to re-arm kill switch: {}", e));
+        }
+    }
+
+    fn send_command(&mut self, command: OmsCommand) {
+        if let Err(e) = self.order_tx.send(command) {
+            error!("Failed to send command to OMS: {}", e);
+            self.log_message(format!("Failed to send command to OMS: {}", e));
+        }
+    }
+
     fn render_orders_table(&mut self, ui: &mut egui::Ui) {
+        let mut action = None;
         ui.group(|ui| {
             ui.heading("Current Orders");
             ScrollArea::vertical().max_height(200.0).show(ui, |ui| {
                 egui::Grid::new("orders_grid")
-                    .num_columns(8) 
+                    .num_columns(9) 
                     .spacing([20.0, 4.0])
                     .striped(true)
                     .show(ui, |ui| {
@@ -235,6 +567,7 @@ impl AlgoApp {
                         ui.strong("Price");
                         ui.strong("TIF");
                         ui.strong("Status");
+                        ui.strong("Actions");
                         ui.end_row();
 
                         for order in &self.orders {
@@ -245,7 +578,23 @@ impl AlgoApp {
                             ui.label(format!("{:.2}", order.quantity));
                             ui.label(format!("{:.2}", order.price.unwrap_or(0.0)));
                             ui.label(format!("{:?}", order.tif));
-                            ui.label(format!("{:?}", order.state));
+                            match self.rejection_reasons.get(&order.order_id) {
+                                Some(reason) => {
+                                    ui.label(RichText::new(format!("{:?}: {}", order.state, reason)).color(Color32::LIGHT_RED));
+                                }
+                                None => {
+                                    ui.label(format!("{:?}", order.state));
+                                }
+                            }
+                            ui.horizontal(|ui| {
+                                let working = order.state.is_working();
+                                if ui.add_enabled(working, egui::Button::new("Cancel")).clicked() {
+                                    action = Some(OrderAction::Cancel(order.order_id));
+                                }
+                                if ui.add_enabled(working, egui::Button::new("Amend")).clicked() {
+                                    action = Some(OrderAction::StartAmend(order.order_id));
+                                }
+                            });
                             ui.end_row();
                         }
                         if self.orders.is_empty() {
@@ -254,7 +603,202 @@ impl AlgoApp {
                         }
                     });
             });
+
+            self.render_amend_editor(ui);
+        });
+
+        match action {
+            Some(OrderAction::Cancel(order_id)) => {
+                self.log_message(format!("Cancel requested for order {}", order_id));
+                self.send_command(OmsCommand::Cancel { order_id });
+            }
+            Some(OrderAction::StartAmend(order_id)) => {
+                if let Some(order) = self.orders.iter().find(|o| o.order_id == order_id) {
+                    self.amend = Some(AmendDraft {
+                        order_id,
+                        price: order.price.map(|p| p.to_string()).unwrap_or_default(),
+                        quantity: order.quantity.to_string(),
+                    });
+                }
+            }
+            None => {}
+        }
+    }
+
+    fn render_positions_panel(&mut self, ui: &mut egui::Ui) {
+        let pnl_color = |pnl: f64| if pnl >= 0.0 { Color32::LIGHT_GREEN } else { Color32::LIGHT_RED };
+        let mut flatten = None;
+        ui.group(|ui| {
+            ui.heading("Positions");
+            let mut positions: Vec<&Position> = self.positions.values().collect();
+            positions.sort_by(|a, b| a.symbol.cmp(&b.symbol));
+            let halted = self.kill_switch.is_some();
+
+            egui::Grid::new("positions_grid").num_columns(7).spacing([20.0, 4.0]).striped(true).show(ui, |ui| {
+                for header in ["Symbol", "Qty", "Avg Cost", "Last", "Unrealized", "Realized", ""] {
+                    ui.strong(header);
+                }
+                ui.end_row();
+
+                for pos in &positions {
+                    ui.label(&pos.symbol);
+                    ui.label(format!("{:.4}", pos.quantity));
+                    ui.label(format!("{:.2}", pos.avg_cost));
+                    ui.label(format!("{:.2}", pos.last_price));
+                    ui.label(RichText::new(format!("{:.2}", pos.pnl_unrealized)).color(pnl_color(pos.pnl_unrealized)));
+                    ui.label(RichText::new(format!("{:.2}", pos.pnl_realized)).color(pnl_color(pos.pnl_realized)));
+                    let button = ui
+                        .add_enabled(!halted && !pos.is_flat(), egui::Button::new("Flatten"))
+                        .on_hover_text("Send a market order for the opposite quantity");
+                    if button.clicked() {
+                        flatten = Some((pos.symbol.clone(), pos.quantity));
+                    }
+                    ui.end_row();
+                }
+                if positions.is_empty() {
+                    ui.label("No positions.");
+                    ui.end_row();
+                }
+            });
+
+            let unrealized: f64 = positions.iter().map(|p| p.pnl_unrealized).sum();
+            let realized: f64 = positions.iter().map(|p| p.pnl_realized).sum();
+            ui.separator();
+            ui.horizontal(|ui| {
+                ui.strong("Account:");
+                ui.label("Unrealized");
+                ui.label(RichText::new(format!("{:.2}", unrealized)).color(pnl_color(unrealized)));
+                ui.label("Realized");
+                ui.label(RichText::new(format!("{:.2}", realized)).color(pnl_color(realized)));
+                ui.label("Total");
+                ui.label(RichText::new(format!("{:.2}", unrealized + realized)).strong().color(pnl_color(unrealized + realized)));
+            });
+            self.pnl_history.show(ui);
+        });
+
+        if let Some((symbol, quantity)) = flatten {
+            self.flatten_position(symbol, quantity);
+        }
+    }
+
+    fn flatten_position(&mut self, symbol: String, quantity: f64) {
+        let side = if quantity > 0.0 { Side::Sell } else { Side::Buy };
+        self.log_message(format!("Flatten requested: {:?} {} {} at market", side, quantity.abs(), symbol));
+        let order = Order::new(symbol, side, OrderType::Market, quantity.abs(), None, TimeInForce::IOC);
+        self.send_command(OmsCommand::New(order));
+    }
+
+    fn render_amend_editor(&mut self, ui: &mut egui::Ui) {
+        let Some(draft) = self.amend.as_mut() else {
+            return;
+        };
+
+        let mut submit = false;
+        let mut discard = false;
+        ui.separator();
+        ui.horizontal(|ui| {
+            ui.label(format!("Amend {}:", draft.order_id.simple()));
+            ui.label("Price:");
+            ui.add(egui::TextEdit::singleline(&mut draft.price).desired_width(80.0));
+            ui.label("Qty:");
+            ui.add(egui::TextEdit::singleline(&mut draft.quantity).desired_width(80.0));
+            submit = ui.button("Submit Amend").clicked();
+            discard = ui.button("Discard").clicked();
+        });
+
+        if discard {
+            self.amend = None;
+        } else if submit {
+            let Some(draft) = self.amend.take() else {
+                return;
+            };
+            let Some(order) = self.orders.iter().find(|o| o.order_id == draft.order_id) else {
+                return;
+            };
+            let new_price = draft.price.parse::<f64>().ok().filter(|p| Some(*p) != order.price);
+            let new_quantity = draft.quantity.parse::<f64>().ok().filter(|q| *q != order.quantity);
+            self.log_message(format!("Amend requested for order {}: price {:?}, qty {:?}", draft.order_id, new_price, new_quantity));
+            self.send_command(OmsCommand::Replace {
+                order_id: draft.order_id,
+                new_price,
+                new_quantity,
+            });
+        }
+    }
+
+    fn send_replay_command(&mut self, command: ReplayCommand) {
+        let Some(replay) = &self.replay else {
+            return;
+        };
+        if let Err(e) = replay.command_tx.send(command) {
+            error!("Failed to send replay command: {}", e);
+            self.log_message(format!("Error: {}", e));
+        }
+    }
+
+    fn render_replay_controls(&mut self, ui: &mut egui::Ui) {
+        const SPEEDS: [ReplaySpeed; 6] = [
+            ReplaySpeed::Multiplier(1.0),
+            ReplaySpeed::Multiplier(2.0),
+            ReplaySpeed::Multiplier(5.0),
+            ReplaySpeed::Multiplier(10.0),
+            ReplaySpeed::Multiplier(100.0),
+            ReplaySpeed::AsFastAsPossible,
+        ];
+        let Some(status) = self.replay_status.clone() else {
+            ui.label("Replay: loading...");
+            return;
+        };
+
+        let mut commands = Vec::new();
+        ui.horizontal(|ui| {
+            ui.label(RichText::new("REPLAY").strong().color(Color32::GOLD));
+            let play_label = if status.paused { "▶ Play" } else { "⏸ Pause" };
+            if ui.add_enabled(!status.finished() || status.paused, egui::Button::new(play_label)).clicked() {
+                commands.push(if status.paused { ReplayCommand::Play } else { ReplayCommand::Pause });
+            }
+
+            let mut speed = status.speed;
+            egui::ComboBox::from_id_source("replay_speed")
+                .selected_text(speed.to_string())
+                .show_ui(ui, |ui| {
+                    for option in SPEEDS {
+                        ui.selectable_value(&mut speed, option, option.to_string());
+                    }
+                });
+            if speed != status.speed {
+                commands.push(ReplayCommand::SetSpeed(speed));
+            }
+
+            let span = (status.end - status.start).num_milliseconds().max(1) as f64 / 1000.0;
+            let current = (status.position - status.start).num_milliseconds() as f64 / 1000.0;
+            let mut seek = self.replay_seek.unwrap_or(current);
+            let start = status.start;
+            let slider = egui::Slider::new(&mut seek, 0.0..=span)
+                .show_value(false)
+                .custom_formatter(move |secs, _| (start + chrono::Duration::milliseconds((secs * 1000.0) as i64)).format("%H:%M:%S").to_string());
+            let response = ui.add_sized([ui.available_width() * 0.6, 18.0], slider);
+            if response.dragged() {
+                self.replay_seek = Some(seek);
+            } else if response.drag_stopped() || response.changed() {
+                self.replay_seek = None;
+                if seek < current {
+                    self.log_message("Replay can only seek forward; restart it to watch earlier data again.".to_string());
+                } else {
+                    commands.push(ReplayCommand::Seek(status.start + chrono::Duration::milliseconds((seek * 1000.0) as i64)));
+                }
+            }
+
+            ui.label(format!(
+                "{} ({}/{})",
+                status.position.format("%Y-%m-%d %H:%M:%S%.3f"),
+                status.sent,
+                status.total
+            ));
         });
+        for command in commands {
+            self.send_replay_command(command);
+        }
     }
 
     fn render_app_log_panel(&mut self, ui: &mut egui::Ui) {
@@ -274,10 +818,18 @@ impl AlgoApp {
 impl App for AlgoApp {
     fn update(&mut self, ctx: &Context, _frame: &mut eframe::Frame) {
         self.handle_market_data_updates();
+        self.handle_candle_updates();
+        self.handle_connection_status();
+        self.handle_replay_status();
         self.handle_oms_updates();
 
         ctx.request_repaint();
 
+        let kill_shortcut = egui::KeyboardShortcut::new(egui::Modifiers::CTRL | egui::Modifiers::SHIFT, egui::Key::K);
+        if ctx.input_mut(|i| i.consume_shortcut(&kill_shortcut)) && self.kill_switch.is_none() {
+            self.trigger_kill_switch("ui-shortcut");
+        }
+
         TopBottomPanel::top("top_panel").show(ctx, |ui| {
             ui.add_space(5.0);
             egui::menu::bar(ui, |ui| {
@@ -286,12 +838,39 @@ impl App for AlgoApp {
                 });
 
                 ui.with_layout(Layout::right_to_left(egui::Align::Center), |ui| {
+                    if self.kill_switch.is_some() {
+                        if ui.button(RichText::new("Re-arm Trading").strong()).clicked() {
+                            self.rearm_kill_switch();
+                        }
+                        ui.label(RichText::new("HALTED").strong().color(Color32::RED));
+                    } else {
+                        let kill = egui::Button::new(RichText::new("KILL SWITCH").strong().color(Color32::WHITE))
+                            .fill(Color32::DARK_RED);
+                        if ui.add(kill).on_hover_text("Stop strategies and cancel all orders (Ctrl+Shift+K)").clicked() {
+                            self.trigger_kill_switch("ui-button");
+                        }
+                    }
+                    ui.separator();
                     ui.label(format!("Strategy Status: {}", self.strategy_status));
+                    ui.separator();
+                    // Right-to-left layout: iterate in reverse to list venues in order.
+                    for (venue, status) in self.md_status.iter().rev() {
+                        let md_color = match status {
+                            ConnectionStatus::Connected => Color32::LIGHT_GREEN,
+                            ConnectionStatus::Connecting | ConnectionStatus::Reconnecting { .. } => Color32::YELLOW,
+                            ConnectionStatus::GaveUp { .. } | ConnectionStatus::Stopped => Color32::LIGHT_RED,
+                        };
+                        ui.label(RichText::new(format!("{}: {}", venue, status)).color(md_color));
+                    }
+                    ui.label("Market Data:");
+                    ui.separator();
                     if ui.button("Start Strategy").clicked() {
                         self.log_message("Strategy start button clicked.".to_string());
                         if let Err(e) = self.strategy_tx.send(StrategyControl::Start) {
                             error!("Failed to send Start Strategy command: {}", e); 
                             self.log_message(format!("Error: {}", e));
+                        } else {
+                            self.strategy_status = "Running".to_string();
                         }
                     }
                     if ui.button("Stop Strategy").clicked() {
@@ -299,6 +878,8 @@ impl App for AlgoApp {
                         if let Err(e) = self.strategy_tx.send(StrategyControl::Stop) {
                             error!("Failed to send Stop Strategy command: {}", e); 
                             self.log_message(format!("Error: {}", e));
+                        } else {
+                            self.strategy_status = "Stopped".to_string();
                         }
                     }
                 });
@@ -306,6 +887,14 @@ impl App for AlgoApp {
             ui.add_space(5.0);
         });
 
+        if self.replay.is_some() {
+            TopBottomPanel::bottom("replay_panel").show(ctx, |ui| {
+                ui.add_space(5.0);
+                self.render_replay_controls(ui);
+                ui.add_space(5.0);
+            });
+        }
+
         CentralPanel::default().show(ctx, |ui| {
             ui.heading("Dashboard");
             ui.add_space(10.0);
@@ -317,16 +906,30 @@ impl App for AlgoApp {
                     ui.vertical(|ui| {
                         self.render_market_data_panel(ui);
                         ui.add_space(10.0);
+                        self.render_order_book_panel(ui);
+                        ui.add_space(10.0);
                         self.render_order_entry_panel(ui);
                     });
   
                     ui.vertical(|ui| {
+                        self.render_watchlist(ui);
+                        ui.add_space(10.0);
+                        self.tape.show(ui, self.selected_symbol.as_deref());
+                        ui.add_space(10.0);
                         self.render_orders_table(ui);
                         ui.add_space(10.0);
+                        self.render_positions_panel(ui);
+                        ui.add_space(10.0);
                         self.render_app_log_panel(ui);
                     });
                     ui.end_row();
                 });
         });
     }
-}
\ No newline at end of file
+}
+/// `HH:MM:SS` for a plot x value in Unix seconds.
+fn time_label(seconds: f64) -> String {
+    chrono::DateTime::from_timestamp_millis((seconds * 1000.0) as i64)
+        .map(|t| t.format("%H:%M:%S").to_string())
+        .unwrap_or_default()
+}
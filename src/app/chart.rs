@@ -0,0 +1,177 @@
+use std::collections::{BTreeMap, HashMap};
+
+use chrono::{DateTime, Utc};
+use egui::{Color32, Id, RichText, Stroke};
+use egui_plot::{Bar, BarChart, BoxElem, BoxPlot, BoxSpread, Legend, MarkerShape, Plot, PlotPoint, Points};
+
+use crate::config::BarSpec;
+use crate::data_model::{Candlestick, Side};
+use crate::oms::Fill;
+use super::time_label;
+
+/// One symbol's bars of one type by start time, with whether each has closed.
+pub type BarSeries = BTreeMap<DateTime<Utc>, (Candlestick, bool)>;
+
+const UP: Color32 = Color32::from_rgb(38, 166, 154);
+const DOWN: Color32 = Color32::from_rgb(239, 83, 80);
+
+/// Candlestick chart with a volume pane. Each symbol and bar type keeps its
+/// own zoom and pan; new bars don't reset it. Double-click or "Reset view"
+/// returns to following the data.
+#[derive(Default)]
+pub struct CandleChart {
+    spec: Option<BarSpec>,
+    reset: bool,
+}
+
+impl CandleChart {
+    pub fn show(
+        &mut self,
+        ui: &mut egui::Ui,
+        symbol: Option<&str>,
+        specs: &[BarSpec],
+        candles: &HashMap<(String, BarSpec), BarSeries>,
+        fills: &[Fill],
+    ) {
+        if self.spec.is_none_or(|s| !specs.contains(&s)) {
+            // Minute bars are the most useful default when configured.
+            self.spec = specs.iter().find(|s| **s == BarSpec::Time(60)).or(specs.first()).copied();
+        }
+
+        ui.horizontal(|ui| {
+            ui.label("Interval:");
+            egui::ComboBox::from_id_source("chart_interval")
+                .selected_text(self.spec.map(|s| s.to_string()).unwrap_or_else(|| "-".to_string()))
+                .show_ui(ui, |ui| {
+                    for spec in specs {
+                        ui.selectable_value(&mut self.spec, Some(*spec), spec.to_string());
+                    }
+                });
+            if ui.button("Reset view").clicked() {
+                self.reset = true;
+            }
+        });
+
+        let (Some(symbol), Some(spec)) = (symbol, self.spec) else {
+            ui.label("No candles yet.");
+            return;
+        };
+        let Some(series) = candles.get(&(symbol.to_string(), spec)).filter(|s| !s.is_empty()) else {
+            ui.label(format!("No {} candles for {} yet.", spec, symbol));
+            return;
+        };
+
+        let width = bar_width(spec, series);
+        let mut ohlc = Vec::with_capacity(series.len());
+        let mut volume = Vec::with_capacity(series.len());
+        for (start, (candle, closed)) in series {
+            let x = seconds(*start) + width / 2.0;
+            let mut color = if candle.close >= candle.open { UP } else { DOWN };
+            if !closed {
+                color = color.gamma_multiply(0.5);
+            }
+            let (body_low, body_high) = (candle.open.min(candle.close), candle.open.max(candle.close));
+            ohlc.push(
+                BoxElem::new(x, BoxSpread::new(candle.low, body_low, candle.close, body_high, candle.high))
+                    .name(format!(
+                        "{}\nO {:.2}  H {:.2}\nL {:.2}  C {:.2}",
+                        start.format("%H:%M:%S"),
+                        candle.open,
+                        candle.high,
+                        candle.low,
+                        candle.close
+                    ))
+                    .box_width(width * 0.8)
+                    .whisker_width(0.0)
+                    .fill(color)
+                    .stroke(Stroke::new(1.0, color)),
+            );
+            volume.push(Bar::new(x, candle.volume).width(width * 0.8).fill(color.gamma_multiply(0.6)));
+        }
+
+        let (buys, sells): (Vec<&Fill>, Vec<&Fill>) =
+            fills.iter().filter(|f| f.symbol == symbol).partition(|f| f.side == Side::Buy);
+        let points = |fills: Vec<&Fill>| -> Vec<[f64; 2]> {
+            fills.iter().map(|f| [seconds(f.timestamp), f.price]).collect()
+        };
+        let (buys, sells) = (points(buys), points(sells));
+
+        let link = Id::new(("candle_chart", symbol, spec));
+        let mut price_plot = Plot::new(("candle_price", symbol, spec))
+            .height(260.0)
+            .link_axis(link, true, false)
+            .link_cursor(link, true, false)
+            .x_axis_formatter(|mark, _, _| time_label(mark.value))
+            .label_formatter(|_, value| format!("{}\n{:.2}", time_label(value.x), value.y))
+            .legend(Legend::default());
+        let mut volume_plot = Plot::new(("candle_volume", symbol, spec))
+            .height(80.0)
+            .link_axis(link, true, false)
+            .link_cursor(link, true, false)
+            .include_y(0.0)
+            .show_axes([false, true])
+            .x_axis_formatter(|mark, _, _| time_label(mark.value))
+            .label_formatter(|_, value| format!("{}\nvol {:.4}", time_label(value.x), value.y));
+        if std::mem::take(&mut self.reset) {
+            price_plot = price_plot.reset();
+            volume_plot = volume_plot.reset();
+        }
+
+        let hovered = price_plot
+            .show(ui, |plot_ui| {
+                plot_ui.box_plot(
+                    BoxPlot::new(ohlc)
+                        .name(format!("{} {}", symbol, spec))
+                        .element_formatter(Box::new(|elem, _| elem.name.clone())),
+                );
+                if !buys.is_empty() {
+                    plot_ui.points(Points::new(buys).name("Buys").shape(MarkerShape::Up).filled(true).radius(6.0).color(Color32::LIGHT_BLUE));
+                }
+                if !sells.is_empty() {
+                    plot_ui.points(Points::new(sells).name("Sells").shape(MarkerShape::Down).filled(true).radius(6.0).color(Color32::GOLD));
+                }
+                plot_ui.pointer_coordinate()
+            })
+            .inner;
+        volume_plot.show(ui, |plot_ui| plot_ui.bar_chart(BarChart::new(volume).name("Volume")));
+
+        ui.label(readout(hovered, series, width).unwrap_or_else(|| RichText::new("Hover the chart for OHLCV").weak()));
+    }
+}
+
+fn seconds(time: DateTime<Utc>) -> f64 {
+    time.timestamp_millis() as f64 / 1000.0
+}
+
+/// Bar width in seconds: the interval for time bars, the typical spacing
+/// for activity bars.
+fn bar_width(spec: BarSpec, series: &BarSeries) -> f64 {
+    if let BarSpec::Time(secs) = spec {
+        return secs as f64;
+    }
+    let starts: Vec<f64> = series.keys().map(|t| seconds(*t)).collect();
+    let mut gaps: Vec<f64> = starts.windows(2).map(|w| w[1] - w[0]).filter(|g| *g > 0.0).collect();
+    gaps.sort_by(f64::total_cmp);
+    gaps.get(gaps.len() / 2).copied().unwrap_or(1.0)
+}
+
+/// OHLCV of the bar under the crosshair.
+fn readout(pointer: Option<PlotPoint>, series: &BarSeries, width: f64) -> Option<RichText> {
+    let pointer = pointer?;
+    let time = DateTime::from_timestamp_millis((pointer.x * 1000.0) as i64)?;
+    let (start, (candle, closed)) = series.range(..=time).next_back()?;
+    if pointer.x > seconds(*start) + width {
+        return None;
+    }
+    Some(RichText::new(format!(
+        "{}  O {:.2}  H {:.2}  L {:.2}  C {:.2}  V {:.4}{}   |   cursor {:.2}",
+        start.format("%Y-%m-%d %H:%M:%S"),
+        candle.open,
+        candle.high,
+        candle.low,
+        candle.close,
+        candle.volume,
+        if *closed { "" } else { " (forming)" },
+        pointer.y
+    )).monospace())
+}
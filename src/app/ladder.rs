@@ -0,0 +1,119 @@
+use std::collections::BTreeMap;
+
+use egui::{Color32, RichText};
+use uuid::Uuid;
+
+use crate::data_model::{Order, Side};
+use crate::market_data::order_book::PriceLevel;
+
+/// What a click on the ladder asks for.
+pub enum LadderAction {
+    Place { side: Side, price: f64 },
+    Cancel(Vec<Uuid>),
+}
+
+/// One price row: book size on each side and our open orders there.
+#[derive(Default)]
+struct Row {
+    price: f64,
+    bid: Option<f64>,
+    ask: Option<f64>,
+    our_bids: Vec<(Uuid, f64)>,
+    our_asks: Vec<(Uuid, f64)>,
+}
+
+/// Price ladder, best prices in the middle. Clicking a bid or ask cell
+/// places at that price; clicking our own size cancels everything we have
+/// working there. Our orders priced between the shown levels get their own
+/// rows; the rest are counted below the ladder.
+pub fn show(ui: &mut egui::Ui, bids: &[PriceLevel], asks: &[PriceLevel], orders: &[Order], trading: bool) -> Option<LadderAction> {
+    let mut rows: BTreeMap<i64, Row> = BTreeMap::new();
+    for (levels, is_bid) in [(bids, true), (asks, false)] {
+        for level in levels {
+            let row = rows.entry(tick(level.price)).or_insert_with(|| Row { price: level.price, ..Default::default() });
+            if is_bid {
+                row.bid = Some(level.quantity);
+            } else {
+                row.ask = Some(level.quantity);
+            }
+        }
+    }
+
+    let range = rows.keys().next().copied().zip(rows.keys().next_back().copied());
+    let mut outside = 0;
+    for order in orders.iter().filter(|o| !o.state.is_terminal()) {
+        let Some(price) = order.price else { continue };
+        let key = tick(price);
+        if !range.is_some_and(|(low, high)| (low..=high).contains(&key)) {
+            outside += 1;
+            continue;
+        }
+        let row = rows.entry(key).or_insert_with(|| Row { price, ..Default::default() });
+        // Only working orders can be canceled; pending ones are shown but skipped.
+        let entry = (if order.state.is_working() { order.order_id } else { Uuid::nil() }, order.quantity);
+        match order.side {
+            Side::Buy => row.our_bids.push(entry),
+            Side::Sell => row.our_asks.push(entry),
+        }
+    }
+
+    let mut action = None;
+    egui::Grid::new("dom_ladder").striped(true).num_columns(5).spacing([12.0, 2.0]).show(ui, |ui| {
+        for header in ["Ours", "Bid Qty", "Price", "Ask Qty", "Ours"] {
+            ui.label(RichText::new(header).strong());
+        }
+        ui.end_row();
+
+        for row in rows.values().rev() {
+            if let Some(ids) = our_cell(ui, &row.our_bids, trading) {
+                action = Some(LadderAction::Cancel(ids));
+            }
+            if size_cell(ui, row.bid, Color32::LIGHT_GREEN, trading).on_hover_text(format!("Buy at {:.2}", row.price)).clicked() {
+                action = Some(LadderAction::Place { side: Side::Buy, price: row.price });
+            }
+            let price_color = match (row.bid, row.ask) {
+                (Some(_), None) => Color32::LIGHT_GREEN,
+                (None, Some(_)) => Color32::LIGHT_RED,
+                _ => Color32::GRAY,
+            };
+            ui.label(RichText::new(format!("{:.2}", row.price)).monospace().color(price_color));
+            if size_cell(ui, row.ask, Color32::LIGHT_RED, trading).on_hover_text(format!("Sell at {:.2}", row.price)).clicked() {
+                action = Some(LadderAction::Place { side: Side::Sell, price: row.price });
+            }
+            if let Some(ids) = our_cell(ui, &row.our_asks, trading) {
+                action = Some(LadderAction::Cancel(ids));
+            }
+            ui.end_row();
+        }
+    });
+    if outside > 0 {
+        ui.label(RichText::new(format!("{} open orders priced outside the ladder", outside)).weak());
+    }
+    action
+}
+
+/// Prices as integer keys, so levels and order prices line up.
+fn tick(price: f64) -> i64 {
+    (price * 1e8).round() as i64
+}
+
+fn size_cell(ui: &mut egui::Ui, size: Option<f64>, color: Color32, trading: bool) -> egui::Response {
+    let text = size.map(|q| format!("{:.4}", q)).unwrap_or_default();
+    ui.add_enabled(trading, egui::Button::new(RichText::new(text).monospace().color(color)).frame(false).min_size(egui::vec2(70.0, 0.0)))
+}
+
+/// Our size at a level; returns the orders to cancel when clicked.
+fn our_cell(ui: &mut egui::Ui, orders: &[(Uuid, f64)], trading: bool) -> Option<Vec<Uuid>> {
+    if orders.is_empty() {
+        ui.label("");
+        return None;
+    }
+    let total: f64 = orders.iter().map(|(_, q)| q).sum();
+    let ids: Vec<Uuid> = orders.iter().map(|(id, _)| *id).filter(|id| !id.is_nil()).collect();
+    let button = egui::Button::new(RichText::new(format!("{:.4}", total)).monospace().strong().color(Color32::BLACK))
+        .fill(Color32::GOLD);
+    let response = ui
+        .add_enabled(trading && !ids.is_empty(), button)
+        .on_hover_text(format!("{} of our orders here; click to cancel", orders.len()));
+    response.clicked().then_some(ids)
+}
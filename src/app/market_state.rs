@@ -0,0 +1,94 @@
+use std::collections::{HashMap, VecDeque};
+
+use chrono::{DateTime, Duration, DurationRound, Utc};
+
+use crate::data_model::MarketDataUpdate;
+
+/// Trades of one minute, for the rolling 24h statistics.
+#[derive(Debug, Clone)]
+pub struct MinuteBar {
+    pub start: DateTime<Utc>,
+    pub open: f64,
+    pub close: f64,
+    pub volume: f64,
+}
+
+/// What the UI knows about one symbol. Windows are measured in market data
+/// time, so they also hold during replay.
+#[derive(Debug, Clone, Default)]
+pub struct SymbolState {
+    pub last_price: Option<f64>,
+    pub last_quantity: Option<f64>,
+    pub bid: Option<(f64, f64)>,
+    pub ask: Option<(f64, f64)>,
+    pub updated_at: Option<DateTime<Utc>>,
+    /// Per-minute trade history covering the last 24h.
+    pub history: VecDeque<MinuteBar>,
+}
+
+impl SymbolState {
+    fn apply(&mut self, update: &MarketDataUpdate) {
+        self.updated_at = Some(self.updated_at.map_or(update.timestamp, |t| t.max(update.timestamp)));
+        if let Some(bid) = update.bid_price {
+            self.bid = Some((bid, update.bid_quantity.unwrap_or_default()));
+        }
+        if let Some(ask) = update.ask_price {
+            self.ask = Some((ask, update.ask_quantity.unwrap_or_default()));
+        }
+        let Some(price) = update.last_price else {
+            return;
+        };
+        let quantity = update.last_quantity.unwrap_or_default();
+        self.last_price = Some(price);
+        self.last_quantity = update.last_quantity;
+
+        let start = update.timestamp.duration_trunc(Duration::minutes(1)).unwrap_or(update.timestamp);
+        match self.history.back_mut() {
+            Some(bar) if bar.start == start => {
+                bar.close = price;
+                bar.volume += quantity;
+            }
+            // Trades for minutes already passed only add volume.
+            Some(bar) if bar.start > start => {
+                if let Some(bar) = self.history.iter_mut().rev().find(|b| b.start <= start) {
+                    bar.volume += quantity;
+                }
+            }
+            _ => self.history.push_back(MinuteBar { start, open: price, close: price, volume: quantity }),
+        }
+        let cutoff = start - Duration::hours(24);
+        while self.history.front().is_some_and(|bar| bar.start <= cutoff) {
+            self.history.pop_front();
+        }
+    }
+
+    /// Change from the first price within the last 24h, as a fraction.
+    pub fn change_24h(&self) -> Option<f64> {
+        let open = self.history.front()?.open;
+        let last = self.last_price?;
+        (open != 0.0).then(|| (last - open) / open)
+    }
+
+    pub fn volume_24h(&self) -> f64 {
+        self.history.iter().map(|bar| bar.volume).sum()
+    }
+
+    pub fn spread(&self) -> Option<f64> {
+        Some(self.ask?.0 - self.bid?.0)
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct MarketState {
+    symbols: HashMap<String, SymbolState>,
+}
+
+impl MarketState {
+    pub fn apply(&mut self, update: &MarketDataUpdate) {
+        self.symbols.entry(update.symbol.to_uppercase()).or_default().apply(update);
+    }
+
+    pub fn get(&self, symbol: &str) -> Option<&SymbolState> {
+        self.symbols.get(&symbol.to_uppercase())
+    }
+}
@@ -0,0 +1,63 @@
+use std::collections::{HashMap, VecDeque};
+
+use chrono::{DateTime, Duration, Utc};
+use egui::Color32;
+use egui_plot::{Legend, Line, Plot, PlotPoints};
+
+use crate::oms::position::Position;
+use super::time_label;
+
+/// Account PnL at one moment.
+#[derive(Debug, Clone, Copy)]
+struct PnlPoint {
+    time: DateTime<Utc>,
+    realized: f64,
+    unrealized: f64,
+}
+
+/// Account PnL over the current UTC day, one point per second at most.
+#[derive(Default)]
+pub struct PnlHistory {
+    points: VecDeque<PnlPoint>,
+}
+
+impl PnlHistory {
+    /// Records the totals across `positions`. Updates within a second of the
+    /// last point replace it; a new day starts a new history.
+    pub fn record(&mut self, time: DateTime<Utc>, positions: &HashMap<String, Position>) {
+        let point = PnlPoint {
+            time,
+            realized: positions.values().map(|p| p.pnl_realized).sum(),
+            unrealized: positions.values().map(|p| p.pnl_unrealized).sum(),
+        };
+        match self.points.back_mut() {
+            Some(last) if last.time.date_naive() != time.date_naive() => self.points.clear(),
+            Some(last) if time - last.time < Duration::seconds(1) => {
+                *last = PnlPoint { time: last.time, ..point };
+                return;
+            }
+            _ => {}
+        }
+        self.points.push_back(point);
+    }
+
+    pub fn show(&self, ui: &mut egui::Ui) {
+        if self.points.is_empty() {
+            ui.label("No PnL yet today.");
+            return;
+        }
+        let series = |value: fn(&PnlPoint) -> f64| -> PlotPoints {
+            self.points.iter().map(|p| [p.time.timestamp_millis() as f64 / 1000.0, value(p)]).collect()
+        };
+        Plot::new("intraday_pnl")
+            .height(150.0)
+            .legend(Legend::default())
+            .x_axis_formatter(|mark, _, _| time_label(mark.value))
+            .label_formatter(|name, value| format!("{}\n{}\n{:.2}", name, time_label(value.x), value.y))
+            .show(ui, |plot_ui| {
+                plot_ui.line(Line::new(series(|p| p.realized + p.unrealized)).name("Total").color(Color32::LIGHT_BLUE).width(2.0));
+                plot_ui.line(Line::new(series(|p| p.realized)).name("Realized").color(Color32::GRAY));
+            });
+    }
+}
+
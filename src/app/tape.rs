@@ -0,0 +1,111 @@
+use std::collections::{HashMap, VecDeque};
+
+use chrono::{DateTime, Duration, Utc};
+use egui::{Color32, RichText, ScrollArea};
+
+use crate::data_model::{MarketDataUpdate, Side};
+
+/// Prints kept per symbol beyond those inside the rate window.
+const MAX_PRINTS: usize = 500;
+const RATE_WINDOW_SECS: i64 = 10;
+
+#[derive(Debug, Clone)]
+struct Print {
+    time: DateTime<Utc>,
+    price: f64,
+    quantity: f64,
+    aggressor: Option<Side>,
+}
+
+/// Time and sales panel: recent trade prints per symbol, newest first, with
+/// prints at or above the highlight size emphasised. The trade and volume
+/// rates cover the `RATE_WINDOW_SECS` before the newest print's exchange
+/// timestamp rather than the wall clock.
+pub struct TradeTape {
+    prints: HashMap<String, VecDeque<Print>>,
+    large_size: String,
+    large_only: bool,
+}
+
+impl Default for TradeTape {
+    fn default() -> Self {
+        Self { prints: HashMap::new(), large_size: "1.0".to_string(), large_only: false }
+    }
+}
+
+impl TradeTape {
+    pub fn apply(&mut self, update: &MarketDataUpdate) {
+        let (Some(price), Some(quantity)) = (update.last_price, update.last_quantity) else {
+            return;
+        };
+        let prints = self.prints.entry(update.symbol.to_uppercase()).or_default();
+        prints.push_back(Print { time: update.timestamp, price, quantity, aggressor: update.aggressor.clone() });
+        let cutoff = update.timestamp - Duration::seconds(RATE_WINDOW_SECS);
+        while prints.len() > MAX_PRINTS && prints.front().is_some_and(|p| p.time < cutoff) {
+            prints.pop_front();
+        }
+    }
+
+    pub fn show(&mut self, ui: &mut egui::Ui, symbol: Option<&str>) {
+        ui.group(|ui| {
+            ui.heading("Time & Sales");
+            ui.horizontal(|ui| {
+                ui.label("Highlight size ≥");
+                ui.add(egui::TextEdit::singleline(&mut self.large_size).desired_width(60.0));
+                ui.checkbox(&mut self.large_only, "Large only");
+            });
+
+            let Some(prints) = symbol.and_then(|s| self.prints.get(&s.to_uppercase())).filter(|p| !p.is_empty()) else {
+                ui.label("No trades yet.");
+                return;
+            };
+            let large = self.large_size.trim().parse::<f64>().ok().filter(|q| *q > 0.0);
+
+            let newest = prints.back().map(|p| p.time).unwrap_or_default();
+            let cutoff = newest - Duration::seconds(RATE_WINDOW_SECS);
+            let recent = prints.iter().rev().take_while(|p| p.time >= cutoff);
+            let (count, volume, buy_volume) = recent.fold((0usize, 0.0, 0.0), |(n, v, b), p| {
+                let bought = if p.aggressor == Some(Side::Buy) { p.quantity } else { 0.0 };
+                (n + 1, v + p.quantity, b + bought)
+            });
+            let window = RATE_WINDOW_SECS as f64;
+            ui.label(format!(
+                "{:.1} trades/s   {:.4} vol/s   buy {:.0}%   (last {}s)",
+                count as f64 / window,
+                volume / window,
+                if volume > 0.0 { buy_volume / volume * 100.0 } else { 0.0 },
+                RATE_WINDOW_SECS
+            ));
+
+            ScrollArea::vertical().id_source("time_and_sales").max_height(200.0).show(ui, |ui| {
+                egui::Grid::new("time_and_sales_grid").striped(true).num_columns(4).show(ui, |ui| {
+                    for header in ["Time", "Price", "Size", "Side"] {
+                        ui.label(RichText::new(header).strong());
+                    }
+                    ui.end_row();
+
+                    for print in prints.iter().rev() {
+                        let is_large = large.is_some_and(|q| print.quantity >= q);
+                        if self.large_only && !is_large {
+                            continue;
+                        }
+                        let (side, color) = match print.aggressor {
+                            Some(Side::Buy) => ("Buy", Color32::LIGHT_GREEN),
+                            Some(Side::Sell) => ("Sell", Color32::LIGHT_RED),
+                            None => ("-", Color32::GRAY),
+                        };
+                        let cell = |text: String| {
+                            let text = RichText::new(text).monospace().color(color);
+                            if is_large { text.strong().background_color(color.gamma_multiply(0.25)) } else { text }
+                        };
+                        ui.label(cell(print.time.format("%H:%M:%S%.3f").to_string()));
+                        ui.label(cell(format!("{:.2}", print.price)));
+                        ui.label(cell(format!("{:.4}", print.quantity)));
+                        ui.label(cell(side.to_string()));
+                        ui.end_row();
+                    }
+                });
+            });
+        });
+    }
+}
@@ -0,0 +1,260 @@
+use std::fs;
+use std::io::Read;
+use std::path::Path;
+
+use anyhow::{anyhow, Context, Result};
+use chrono::{DateTime, Utc};
+use flate2::read::GzDecoder;
+use serde::Deserialize;
+
+use crate::data_model::MarketDataUpdate;
+use crate::market_data::recorder::{RecordedUpdate, UPDATES_PREFIX};
+
+const CSV_COLUMNS: [&str; 8] = [
+    "symbol",
+    "timestamp",
+    "bid_price",
+    "bid_quantity",
+    "ask_price",
+    "ask_quantity",
+    "last_price",
+    "last_quantity",
+];
+
+/// A JSONL line: either a bare update or one written by the recorder.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum JsonlLine {
+    Recorded(RecordedUpdate),
+    Plain(MarketDataUpdate),
+}
+
+/// Loads recorded market data from a `.jsonl` file (one serialized
+/// `MarketDataUpdate` or recorder line per line) or a `.csv` file with a
+/// header naming the `MarketDataUpdate` fields, either optionally gzip'd.
+/// A directory loads every recorder update file in it. Updates are returned
+/// sorted by timestamp.
+pub fn load_market_data(path: &Path) -> Result<Vec<MarketDataUpdate>> {
+    let mut updates = if path.is_dir() {
+        load_recording_dir(path)?
+    } else {
+        load_file(path)?
+    };
+
+    updates.sort_by_key(|u| u.timestamp);
+    Ok(updates)
+}
+
+fn load_file(path: &Path) -> Result<Vec<MarketDataUpdate>> {
+    let bytes = fs::read(path).with_context(|| format!("Failed to read market data file {}", path.display()))?;
+
+    let (content, format_path) = if path.extension().and_then(|e| e.to_str()) == Some("gz") {
+        let mut content = String::new();
+        GzDecoder::new(bytes.as_slice())
+            .read_to_string(&mut content)
+            .with_context(|| format!("Failed to decompress {}", path.display()))?;
+        (content, path.with_extension(""))
+    } else {
+        (String::from_utf8(bytes).with_context(|| format!("{} is not UTF-8", path.display()))?, path.to_path_buf())
+    };
+
+    match format_path.extension().and_then(|e| e.to_str()) {
+        Some("csv") => parse_csv(&content),
+        Some("jsonl") | Some("json") => parse_jsonl(&content),
+        other => Err(anyhow!("Unsupported market data file extension: {:?}", other)),
+    }
+    .with_context(|| format!("Failed to parse {}", path.display()))
+}
+
+fn load_recording_dir(dir: &Path) -> Result<Vec<MarketDataUpdate>> {
+    let mut paths = Vec::new();
+    for entry in fs::read_dir(dir).with_context(|| format!("Failed to read directory {}", dir.display()))? {
+        let path = entry?.path();
+        let is_recording = path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .is_some_and(|n| n.starts_with(UPDATES_PREFIX) && n.ends_with(".jsonl.gz"));
+        if is_recording {
+            paths.push(path);
+        }
+    }
+    if paths.is_empty() {
+        return Err(anyhow!("No {}-*.jsonl.gz recordings found in {}", UPDATES_PREFIX, dir.display()));
+    }
+    // File names sort chronologically; keeps ties in recorded order.
+    paths.sort();
+    let mut updates = Vec::new();
+    for path in paths {
+        updates.extend(load_file(&path)?);
+    }
+    Ok(updates)
+}
+
+pub fn parse_jsonl(content: &str) -> Result<Vec<MarketDataUpdate>> {
+    content
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(i, line)| {
+            let line: JsonlLine = serde_json::from_str(line).with_context(|| format!("Invalid JSON on line {}", i + 1))?;
+            Ok(match line {
+                JsonlLine::Recorded(recorded) => recorded.update,
+                JsonlLine::Plain(update) => update,
+            })
+        })
+        .collect()
+}
+
+pub fn parse_csv(content: &str) -> Result<Vec<MarketDataUpdate>> {
+    let mut lines = content.lines().enumerate().filter(|(_, l)| !l.trim().is_empty());
+
+    let (_, header) = lines.next().ok_or_else(|| anyhow!("CSV file is empty"))?;
+    let columns: Vec<&str> = header.split(',').map(str::trim).collect();
+    let index_of = |name: &str| columns.iter().position(|c| *c == name);
+
+    let symbol_idx = index_of("symbol").ok_or_else(|| anyhow!("CSV header is missing 'symbol'"))?;
+    let timestamp_idx = index_of("timestamp").ok_or_else(|| anyhow!("CSV header is missing 'timestamp'"))?;
+    let field_idx: Vec<Option<usize>> = CSV_COLUMNS[2..].iter().map(|c| index_of(c)).collect();
+
+    lines
+        .map(|(i, line)| {
+            let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+            let get = |idx: Option<usize>| idx.and_then(|i| fields.get(i)).filter(|f| !f.is_empty());
+            let number = |idx: Option<usize>| -> Result<Option<f64>> {
+                get(idx)
+                    .map(|f| f.parse::<f64>().with_context(|| format!("Invalid number '{}' on line {}", f, i + 1)))
+                    .transpose()
+            };
+
+            let symbol = get(Some(symbol_idx))
+                .ok_or_else(|| anyhow!("Missing symbol on line {}", i + 1))?
+                .to_string();
+            let timestamp = parse_timestamp(
+                get(Some(timestamp_idx)).ok_or_else(|| anyhow!("Missing timestamp on line {}", i + 1))?,
+            )
+            .with_context(|| format!("Invalid timestamp on line {}", i + 1))?;
+
+            Ok(MarketDataUpdate {
+                symbol,
+                timestamp,
+                bid_price: number(field_idx[0])?,
+                bid_quantity: number(field_idx[1])?,
+                ask_price: number(field_idx[2])?,
+                ask_quantity: number(field_idx[3])?,
+                last_price: number(field_idx[4])?,
+                last_quantity: number(field_idx[5])?,
+                aggressor: None,
+            })
+        })
+        .collect()
+}
+
+/// Accepts RFC 3339 strings or integer epoch milliseconds.
+fn parse_timestamp(value: &str) -> Result<DateTime<Utc>> {
+    if let Ok(ms) = value.parse::<i64>() {
+        return DateTime::from_timestamp_millis(ms).ok_or_else(|| anyhow!("Timestamp out of range: {}", ms));
+    }
+    Ok(DateTime::parse_from_rfc3339(value)?.with_timezone(&Utc))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use flate2::write::GzEncoder;
+    use flate2::Compression;
+
+    use super::*;
+
+    const CSV: &str = "symbol,timestamp,bid_price,bid_quantity,ask_price,ask_quantity,last_price,last_quantity
+BTCUSDT,2024-06-10T06:13:20.100Z,,,,,67000.5,0.25
+
+ETHUSDT,1718000000000,3500.1,2.0,3500.2,1.0,,
+";
+
+    /// A fresh directory under the system temp dir, removed by the caller.
+    fn temp_dir() -> std::path::PathBuf {
+        let dir = std::env::temp_dir().join(format!("loader_test_{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn gzip(path: &Path, content: &str) {
+        let mut encoder = GzEncoder::new(fs::File::create(path).unwrap(), Compression::default());
+        encoder.write_all(content.as_bytes()).unwrap();
+        encoder.finish().unwrap();
+    }
+
+    #[test]
+    fn csv_rows_with_either_timestamp_format() {
+        let updates = parse_csv(CSV).unwrap();
+        assert_eq!(updates.len(), 2);
+        assert_eq!(updates[0].symbol, "BTCUSDT");
+        assert_eq!(updates[0].timestamp.timestamp_millis(), 1718000000100);
+        assert_eq!((updates[0].last_price, updates[0].bid_price), (Some(67000.5), None));
+        assert_eq!(updates[1].timestamp.timestamp_millis(), 1718000000000);
+        assert_eq!((updates[1].bid_price, updates[1].ask_quantity), (Some(3500.1), Some(1.0)));
+        assert_eq!(updates[1].last_price, None);
+    }
+
+    #[test]
+    fn csv_bad_rows_name_the_line() {
+        let bad_number = "symbol,timestamp,last_price\nBTCUSDT,1718000000000,1.0\nBTCUSDT,1718000000001,abc\n";
+        let err = format!("{:#}", parse_csv(bad_number).unwrap_err());
+        assert!(err.contains("Invalid number 'abc' on line 3"), "{}", err);
+
+        let no_symbol = "symbol,timestamp,last_price\n,1718000000000,1.0\n";
+        assert!(format!("{:#}", parse_csv(no_symbol).unwrap_err()).contains("Missing symbol on line 2"));
+
+        let bad_time = "symbol,timestamp\nBTCUSDT,yesterday\n";
+        assert!(format!("{:#}", parse_csv(bad_time).unwrap_err()).contains("Invalid timestamp on line 2"));
+
+        assert!(parse_csv("timestamp,last_price\n").is_err());
+        assert!(parse_csv("").is_err());
+    }
+
+    #[test]
+    fn jsonl_accepts_plain_and_recorded_lines() {
+        let update = MarketDataUpdate {
+            symbol: "BTCUSDT".to_string(),
+            timestamp: DateTime::from_timestamp_millis(1718000000000).unwrap(),
+            bid_price: None,
+            bid_quantity: None,
+            ask_price: None,
+            ask_quantity: None,
+            last_price: Some(67000.0),
+            last_quantity: Some(1.0),
+            aggressor: None,
+        };
+        let recorded = RecordedUpdate { received_at: update.timestamp, update: update.clone() };
+        let content = format!("{}\n\n{}\n", serde_json::to_string(&update).unwrap(), serde_json::to_string(&recorded).unwrap());
+        let updates = parse_jsonl(&content).unwrap();
+        assert_eq!(updates.len(), 2);
+        assert!(updates.iter().all(|u| u.symbol == "BTCUSDT" && u.last_price == Some(67000.0)));
+
+        let err = format!("{:#}", parse_jsonl("{\"symbol\": 1}\n").unwrap_err());
+        assert!(err.contains("Invalid JSON on line 1"), "{}", err);
+    }
+
+    #[test]
+    fn loads_gzipped_files_sorted_by_time() {
+        let dir = temp_dir();
+        let csv = dir.join("data.csv.gz");
+        gzip(&csv, CSV);
+        let plain = dir.join("data.csv");
+        fs::write(&plain, CSV).unwrap();
+        let unsupported = dir.join("data.txt");
+        fs::write(&unsupported, CSV).unwrap();
+
+        let updates = load_market_data(&csv).unwrap();
+        let plain_updates = load_market_data(&plain).unwrap();
+        let unsupported_err = load_market_data(&unsupported).unwrap_err();
+        let dir_err = load_market_data(&dir).unwrap_err();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(updates.iter().map(|u| u.symbol.as_str()).collect::<Vec<_>>(), vec!["ETHUSDT", "BTCUSDT"]);
+        assert_eq!(plain_updates.len(), 2);
+        assert!(format!("{:#}", unsupported_err).contains("Unsupported market data file extension"));
+        assert!(format!("{:#}", dir_err).contains("recordings found"));
+    }
+}
@@ -0,0 +1,282 @@
+pub mod loader;
+pub mod report;
+
+use std::path::PathBuf;
+
+use anyhow::Result;
+use chrono::{DateTime, Duration, Utc};
+use tracing::{debug, info};
+
+use crate::config::AppConfig;
+use crate::data_model::{MarketDataUpdate, Order};
+use crate::market_data::candles::CandleAggregator;
+use crate::oms::matching::{MatchEvent, MatchingEngine};
+use crate::oms::position::PositionManager;
+use crate::oms::OmsUpdate;
+use crate::strategy::{Strategy, StrategyRegistry};
+
+pub use report::BacktestReport;
+
+const TIMER_INTERVAL_SECS: i64 = 5;
+
+#[derive(Debug, Clone)]
+pub struct BacktestConfig {
+    pub data_path: PathBuf,
+    pub initial_capital: f64,
+    pub equity_out: Option<PathBuf>,
+}
+
+/// Replays recorded market data through a strategy, filling its orders with
+/// the same `MatchingEngine` and `PositionManager` the live OMS uses.
+pub struct Backtester {
+    strategy: Box<dyn Strategy>,
+    strategy_name: String,
+    fills: MatchingEngine,
+    candles: CandleAggregator,
+    positions: PositionManager,
+    initial_capital: f64,
+    equity_curve: Vec<(DateTime<Utc>, f64)>,
+    trade_count: usize,
+    winning_closes: usize,
+    closing_trades: usize,
+    turnover: f64,
+    next_timer: Option<DateTime<Utc>>,
+}
+
+impl Backtester {
+    pub fn new(strategy: Box<dyn Strategy>, matching: MatchingEngine, candles: CandleAggregator, initial_capital: f64) -> Self {
+        let strategy_name = strategy.name().to_string();
+        Self {
+            strategy,
+            strategy_name,
+            fills: matching,
+            candles,
+            positions: PositionManager::new(),
+            initial_capital,
+            equity_curve: Vec::new(),
+            trade_count: 0,
+            winning_closes: 0,
+            closing_trades: 0,
+            turnover: 0.0,
+            next_timer: None,
+        }
+    }
+
+    pub fn run(mut self, updates: &[MarketDataUpdate]) -> BacktestReport {
+        let start_orders = self.strategy.on_start();
+        let start_time = updates.first().map(|u| u.timestamp).unwrap_or_else(Utc::now);
+        self.submit_all(start_orders, start_time);
+
+        for update in updates {
+            self.step(update);
+        }
+
+        let end_time = updates.last().map(|u| u.timestamp).unwrap_or(start_time);
+        let stop_orders = self.strategy.on_stop();
+        self.submit_all(stop_orders, end_time);
+
+        self.finish()
+    }
+
+    fn step(&mut self, update: &MarketDataUpdate) {
+        let now = update.timestamp;
+
+        let events = self.fills.on_market_data(update);
+        self.handle_events(events, now);
+
+        if let Some(last_price) = update.last_price {
+            self.positions.mark_to_market(&update.symbol, last_price);
+        }
+
+        let orders = self.strategy.on_market_data(update);
+        self.submit_all(orders, now);
+
+        for candle in self.candles.on_market_data(update) {
+            let orders = self.strategy.on_candle(&candle);
+            self.submit_all(orders, now);
+        }
+
+        let next_timer = *self.next_timer.get_or_insert(now);
+        if now >= next_timer {
+            let orders = self.strategy.on_timer(now);
+            self.submit_all(orders, now);
+            self.next_timer = Some(now + Duration::seconds(TIMER_INTERVAL_SECS));
+        }
+
+        self.equity_curve.push((now, self.equity()));
+    }
+
+    fn submit_all(&mut self, orders: Vec<Order>, now: DateTime<Utc>) {
+        for order in orders {
+            debug!("Backtest order: {:?} {} {} @ {:?}", order.side, order.quantity, order.symbol, order.price);
+            let events = self.fills.submit(order, now);
+            self.handle_events(events, now);
+        }
+    }
+
+    fn handle_events(&mut self, events: Vec<MatchEvent>, now: DateTime<Utc>) {
+        for event in events {
+            match event {
+                MatchEvent::Fill(fill) => {
+                    let realized_before = self.realized_pnl();
+                    self.positions.update_position(fill.symbol.clone(), fill.side.clone(), fill.quantity, fill.price);
+                    let realized_delta = self.realized_pnl() - realized_before;
+
+                    self.trade_count += 1;
+                    self.turnover += fill.quantity * fill.price;
+                    if realized_delta.abs() > f64::EPSILON {
+                        self.closing_trades += 1;
+                        if realized_delta > 0.0 {
+                            self.winning_closes += 1;
+                        }
+                    }
+
+                    let orders = self.strategy.on_fill(&fill);
+                    self.submit_all(orders, fill.timestamp);
+                }
+                MatchEvent::StateChange(order_id, new_state) => {
+                    self.strategy.on_order_update(&OmsUpdate::OrderStateChange {
+                        order_id,
+                        new_state,
+                        timestamp: now,
+                    });
+                }
+            }
+        }
+    }
+
+    fn realized_pnl(&self) -> f64 {
+        self.positions.get_all_positions().iter().map(|p| p.pnl_realized).sum()
+    }
+
+    fn unrealized_pnl(&self) -> f64 {
+        self.positions.get_all_positions().iter().map(|p| p.pnl_unrealized).sum()
+    }
+
+    fn equity(&self) -> f64 {
+        self.initial_capital + self.realized_pnl() + self.unrealized_pnl()
+    }
+
+    fn finish(self) -> BacktestReport {
+        BacktestReport {
+            max_drawdown: report::max_drawdown(&self.equity_curve),
+            max_drawdown_pct: report::max_drawdown_pct(&self.equity_curve),
+            sharpe_ratio: report::sharpe_ratio(&self.equity_curve),
+            realized_pnl: self.realized_pnl(),
+            unrealized_pnl: self.unrealized_pnl(),
+            win_rate: if self.closing_trades > 0 {
+                self.winning_closes as f64 / self.closing_trades as f64
+            } else {
+                0.0
+            },
+            strategy: self.strategy_name,
+            initial_capital: self.initial_capital,
+            equity_curve: self.equity_curve,
+            trade_count: self.trade_count,
+            turnover: self.turnover,
+        }
+    }
+}
+
+pub fn run_backtest(
+    config: &BacktestConfig,
+    app_config: &AppConfig,
+    registry: &StrategyRegistry,
+) -> Result<BacktestReport> {
+    let updates = loader::load_market_data(&config.data_path)?;
+    info!("Loaded {} market data updates from {}", updates.len(), config.data_path.display());
+
+    let strategy = registry.create(&app_config.strategy)?;
+    let matching = MatchingEngine::new(app_config.oms.simulator.clone());
+    let candles = CandleAggregator::new(&app_config.candles);
+    let report = Backtester::new(strategy, matching, candles, config.initial_capital).run(&updates);
+
+    if let Some(path) = &config.equity_out {
+        report.write_equity_csv(path)?;
+        info!("Equity curve written to {}", path.display());
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+    use crate::config::{CandleConfig, SimulatorConfig};
+    use crate::data_model::{OrderType, Side, TimeInForce};
+
+    /// Sends a market order on the listed update indices.
+    struct Scripted {
+        script: Vec<(usize, Side)>,
+        seen: usize,
+    }
+
+    impl Strategy for Scripted {
+        fn name(&self) -> &str {
+            "Scripted"
+        }
+
+        fn on_market_data(&mut self, update: &MarketDataUpdate) -> Vec<Order> {
+            let index = self.seen;
+            self.seen += 1;
+            self.script
+                .iter()
+                .filter(|(i, _)| *i == index)
+                .map(|(_, side)| Order::new(update.symbol.clone(), side.clone(), OrderType::Market, 1.0, None, TimeInForce::IOC))
+                .collect()
+        }
+    }
+
+    /// A quote one wide around each price, plus a trade at it, a minute apart.
+    fn updates(prices: &[f64]) -> Vec<MarketDataUpdate> {
+        prices
+            .iter()
+            .enumerate()
+            .map(|(i, p)| MarketDataUpdate {
+                symbol: "BTCUSDT".to_string(),
+                timestamp: Utc.timestamp_opt(1_700_000_040 + 60 * i as i64, 0).unwrap(),
+                bid_price: Some(p - 0.5),
+                bid_quantity: Some(10.0),
+                ask_price: Some(p + 0.5),
+                ask_quantity: Some(10.0),
+                last_price: Some(*p),
+                last_quantity: Some(1.0),
+                aggressor: None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn round_trips_produce_trades_win_rate_and_pnl() {
+        let strategy = Scripted {
+            script: vec![(0, Side::Buy), (2, Side::Sell), (3, Side::Buy), (4, Side::Sell), (5, Side::Buy)],
+            seen: 0,
+        };
+        let matching = MatchingEngine::new(SimulatorConfig { slippage_bps: 0.0, partial_fills: true });
+        let candles = CandleAggregator::new(&CandleConfig::default());
+        let report = Backtester::new(Box::new(strategy), matching, candles, 1_000.0)
+            .run(&updates(&[100.0, 101.0, 103.0, 102.0, 99.0, 100.0]));
+
+        // Buy 100.5, sell 102.5 (+2); buy 102.5, sell 98.5 (-4); buy 100.5 and mark at 100.
+        assert_eq!(report.strategy, "Scripted");
+        assert_eq!(report.trade_count, 5);
+        assert_eq!(report.win_rate, 0.5);
+        assert!((report.realized_pnl - -2.0).abs() < 1e-9);
+        assert!((report.unrealized_pnl - -0.5).abs() < 1e-9);
+        assert!((report.turnover - (100.5 + 102.5 + 102.5 + 98.5 + 100.5)).abs() < 1e-9);
+        assert_eq!(report.equity_curve.len(), 6);
+        assert!((report.equity_curve.last().unwrap().1 - 997.5).abs() < 1e-9);
+        assert!(report.max_drawdown > 0.0);
+    }
+
+    #[test]
+    fn no_data_gives_an_empty_report() {
+        let strategy = Scripted { script: Vec::new(), seen: 0 };
+        let matching = MatchingEngine::new(SimulatorConfig::default());
+        let candles = CandleAggregator::new(&CandleConfig::default());
+        let report = Backtester::new(Box::new(strategy), matching, candles, 1_000.0).run(&[]);
+        assert_eq!((report.trade_count, report.win_rate, report.total_pnl()), (0, 0.0, 0.0));
+        assert!(report.equity_curve.is_empty());
+    }
+}
@@ -0,0 +1,173 @@
+use std::fmt;
+use std::io::Write;
+use std::path::Path;
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+
+const MINUTES_PER_YEAR: f64 = 365.0 * 24.0 * 60.0;
+
+#[derive(Debug, Clone)]
+pub struct BacktestReport {
+    pub strategy: String,
+    pub initial_capital: f64,
+    /// Initial capital plus realized and unrealized PnL after every update.
+    pub equity_curve: Vec<(DateTime<Utc>, f64)>,
+    pub realized_pnl: f64,
+    pub unrealized_pnl: f64,
+    pub max_drawdown: f64,
+    /// Largest decline as a fraction of the peak it fell from.
+    pub max_drawdown_pct: f64,
+    pub sharpe_ratio: f64,
+    pub win_rate: f64,
+    pub trade_count: usize,
+    pub turnover: f64,
+}
+
+impl BacktestReport {
+    pub fn total_pnl(&self) -> f64 {
+        self.realized_pnl + self.unrealized_pnl
+    }
+
+    pub fn write_equity_csv(&self, path: &Path) -> Result<()> {
+        let mut file = std::io::BufWriter::new(std::fs::File::create(path)?);
+        writeln!(file, "timestamp,equity")?;
+        for (ts, equity) in &self.equity_curve {
+            writeln!(file, "{},{:.8}", ts.to_rfc3339(), equity)?;
+        }
+        file.flush()?;
+        Ok(())
+    }
+}
+
+impl fmt::Display for BacktestReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Backtest report: {}", self.strategy)?;
+        if let (Some((start, _)), Some((end, _))) = (self.equity_curve.first(), self.equity_curve.last()) {
+            writeln!(f, "  Period:          {} .. {}", start.to_rfc3339(), end.to_rfc3339())?;
+        }
+        writeln!(f, "  Initial capital: {:.2}", self.initial_capital)?;
+        writeln!(f, "  Total PnL:       {:.2}", self.total_pnl())?;
+        writeln!(f, "  Realized PnL:    {:.2}", self.realized_pnl)?;
+        writeln!(f, "  Unrealized PnL:  {:.2}", self.unrealized_pnl)?;
+        writeln!(f, "  Max drawdown:    {:.2} ({:.2}%)", self.max_drawdown, self.max_drawdown_pct * 100.0)?;
+        writeln!(f, "  Sharpe (ann.):   {:.3}", self.sharpe_ratio)?;
+        writeln!(f, "  Win rate:        {:.1}%", self.win_rate * 100.0)?;
+        writeln!(f, "  Trades:          {}", self.trade_count)?;
+        write!(f, "  Turnover:        {:.2}", self.turnover)
+    }
+}
+
+/// Largest peak-to-trough decline of the equity curve, in currency units.
+pub fn max_drawdown(equity: &[(DateTime<Utc>, f64)]) -> f64 {
+    let mut peak = f64::MIN;
+    let mut max_dd: f64 = 0.0;
+    for (_, value) in equity {
+        peak = peak.max(*value);
+        max_dd = max_dd.max(peak - value);
+    }
+    max_dd
+}
+
+/// Largest peak-to-trough decline relative to the peak.
+pub fn max_drawdown_pct(equity: &[(DateTime<Utc>, f64)]) -> f64 {
+    let mut peak = f64::MIN;
+    let mut max_dd: f64 = 0.0;
+    for (_, value) in equity {
+        peak = peak.max(*value);
+        if peak > 0.0 {
+            max_dd = max_dd.max((peak - value) / peak);
+        }
+    }
+    max_dd
+}
+
+/// Annualized Sharpe ratio of per-minute returns, with equity sampled at the
+/// last point of each minute and a zero risk-free rate.
+pub fn sharpe_ratio(equity: &[(DateTime<Utc>, f64)]) -> f64 {
+    let mut samples: Vec<f64> = Vec::new();
+    let mut current_minute = None;
+    for (ts, value) in equity {
+        let minute = ts.timestamp() / 60;
+        if current_minute == Some(minute) {
+            if let Some(last) = samples.last_mut() {
+                *last = *value;
+            }
+        } else {
+            samples.push(*value);
+            current_minute = Some(minute);
+        }
+    }
+
+    let returns: Vec<f64> = samples
+        .windows(2)
+        .map(|w| if w[0] > 0.0 { (w[1] - w[0]) / w[0] } else { 0.0 })
+        .collect();
+    if returns.len() < 2 {
+        return 0.0;
+    }
+
+    let n = returns.len() as f64;
+    let mean = returns.iter().sum::<f64>() / n;
+    let var = returns.iter().map(|r| (r - mean).powi(2)).sum::<f64>() / (n - 1.0);
+    let sd = var.sqrt();
+    if sd > 0.0 {
+        mean / sd * MINUTES_PER_YEAR.sqrt()
+    } else {
+        0.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+
+    /// One point every `step_secs`, starting on a minute boundary.
+    fn curve(values: &[f64], step_secs: i64) -> Vec<(DateTime<Utc>, f64)> {
+        values
+            .iter()
+            .enumerate()
+            .map(|(i, v)| (Utc.timestamp_opt(1_700_000_040 + i as i64 * step_secs, 0).unwrap(), *v))
+            .collect()
+    }
+
+    #[test]
+    fn drawdown_from_the_highest_peak() {
+        let equity = curve(&[100.0, 120.0, 90.0, 110.0, 80.0, 130.0], 60);
+        assert_eq!(max_drawdown(&equity), 40.0);
+        assert!((max_drawdown_pct(&equity) - 40.0 / 120.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn drawdown_of_a_rising_or_empty_curve_is_zero() {
+        assert_eq!(max_drawdown(&curve(&[100.0, 101.0, 105.0], 60)), 0.0);
+        assert_eq!(max_drawdown_pct(&curve(&[100.0, 101.0, 105.0], 60)), 0.0);
+        assert_eq!(max_drawdown(&[]), 0.0);
+        assert_eq!(max_drawdown_pct(&[]), 0.0);
+    }
+
+    #[test]
+    fn sharpe_of_known_minute_returns() {
+        // Returns +10%, -10%, +10%: mean 1/30, sample sd sqrt(0.04/3).
+        let expected = (MINUTES_PER_YEAR / 12.0).sqrt();
+        let equity = curve(&[100.0, 110.0, 99.0, 108.9], 60);
+        assert!((sharpe_ratio(&equity) - expected).abs() < 1e-6);
+
+        // Points within a minute only count with their last value.
+        let sampled: Vec<_> = equity
+            .iter()
+            .flat_map(|(t, v)| [(*t, 1.0), (*t + chrono::Duration::seconds(30), *v)])
+            .collect();
+        assert!((sharpe_ratio(&sampled) - expected).abs() < 1e-6);
+    }
+
+    #[test]
+    fn sharpe_without_variance_or_data_is_zero() {
+        assert_eq!(sharpe_ratio(&curve(&[100.0, 100.0, 100.0, 100.0], 60)), 0.0);
+        assert_eq!(sharpe_ratio(&curve(&[100.0, 110.0], 60)), 0.0);
+        assert_eq!(sharpe_ratio(&curve(&[100.0, 110.0, 120.0], 1)), 0.0);
+        assert_eq!(sharpe_ratio(&[]), 0.0);
+    }
+}
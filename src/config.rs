@@ -1,41 +1,461 @@
 use serde::{Deserialize, Serialize};
 use config::{Config, File, FileFormat};
+use std::collections::HashMap;
+use std::fmt;
 use std::path::Path;
 use anyhow::Result;
-use toml; 
 
 #[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct MarketDataConfig {
+    /// Binance stream endpoint; the combined-stream path and query are built
+    /// from `symbols` and `streams`.
     pub websocket_url: String,
     pub symbols: Vec<String>,
+    #[serde(default = "default_streams")]
+    pub streams: Vec<StreamKind>,
+    #[serde(default = "default_kline_interval")]
+    pub kline_interval: String,
+    #[serde(default)]
+    pub reconnect: ReconnectConfig,
+    #[serde(default)]
+    pub order_book: OrderBookConfig,
+    /// Venue per symbol; symbols not listed stream from Binance.
+    #[serde(default)]
+    pub symbol_venues: HashMap<String, Venue>,
+    #[serde(default)]
+    pub coinbase: CoinbaseConfig,
+}
+
+impl MarketDataConfig {
+    pub fn venue_for(&self, symbol: &str) -> Venue {
+        self.symbol_venues
+            .iter()
+            .find(|(s, _)| s.eq_ignore_ascii_case(symbol))
+            .map(|(_, venue)| *venue)
+            .unwrap_or_default()
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum Venue {
+    #[default]
+    Binance,
+    Coinbase,
+}
+
+impl fmt::Display for Venue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Venue::Binance => write!(f, "Binance"),
+            Venue::Coinbase => write!(f, "Coinbase"),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct CoinbaseConfig {
+    pub websocket_url: String,
+}
+
+impl Default for CoinbaseConfig {
+    fn default() -> Self {
+        Self {
+            websocket_url: "wss://ws-feed.exchange.coinbase.com".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct OrderBookConfig {
+    /// Subscribe to depth diffs and maintain a local L2 book per symbol.
+    pub enabled: bool,
+    /// Binance WebSocket API endpoint used for depth snapshots.
+    pub snapshot_url: String,
+    pub snapshot_limit: u32,
+}
+
+impl Default for OrderBookConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            snapshot_url: "wss://ws-api.binance.com:443/ws-api/v3".to_string(),
+            snapshot_limit: 1000,
+        }
+    }
+}
+
+/// Per-symbol streams to subscribe to. Depth diffs are added when the
+/// order book is enabled.
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum StreamKind {
+    Trade,
+    AggTrade,
+    BookTicker,
+    Kline,
+}
+
+fn default_streams() -> Vec<StreamKind> {
+    vec![StreamKind::Trade]
+}
+
+fn default_kline_interval() -> String {
+    "1m".to_string()
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct ReconnectConfig {
+    pub initial_backoff_ms: u64,
+    pub max_backoff_ms: u64,
+    /// Consecutive failed attempts before giving up; `None` retries forever.
+    pub max_retries: Option<u32>,
+}
+
+impl Default for ReconnectConfig {
+    fn default() -> Self {
+        Self {
+            initial_backoff_ms: 500,
+            max_backoff_ms: 30_000,
+            max_retries: None,
+        }
+    }
 }
 
 #[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct StrategyConfig {
     pub enabled: bool,
     pub name: String,
+    /// Entry threshold: a fraction of the mean for `Percent`, standard deviations for `ZScore`.
     pub mean_reversion_threshold: f64,
+    #[serde(default = "default_window_length")]
+    pub window_length: usize,
+    #[serde(default)]
+    pub mean_type: MeanType,
+    #[serde(default)]
+    pub deviation_mode: DeviationMode,
+    #[serde(default = "default_order_quantity")]
+    pub order_quantity: f64,
+    #[serde(default = "default_max_position")]
+    pub max_position: f64,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Default)]
+pub enum MeanType {
+    #[default]
+    Sma,
+    Ema,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Default)]
+pub enum DeviationMode {
+    #[default]
+    Percent,
+    ZScore,
+}
+
+fn default_window_length() -> usize {
+    50
+}
+
+fn default_order_quantity() -> f64 {
+    0.001
+}
+
+fn default_max_position() -> f64 {
+    0.01
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct OmsConfig {
+    #[serde(default)]
+    pub simulator: SimulatorConfig,
+    #[serde(default)]
+    pub risk: RiskConfig,
+    /// Keep an append-only event journal and replay it on startup.
+    #[serde(default = "default_journal_enabled")]
+    pub journal_enabled: bool,
+    #[serde(default = "default_journal_path")]
+    pub journal_path: String,
+}
+
+fn default_journal_enabled() -> bool {
+    true
+}
+
+fn default_journal_path() -> String {
+    "oms_journal.jsonl".to_string()
+}
+
+impl Default for OmsConfig {
+    fn default() -> Self {
+        Self {
+            simulator: SimulatorConfig::default(),
+            risk: RiskConfig::default(),
+            journal_enabled: default_journal_enabled(),
+            journal_path: default_journal_path(),
+        }
+    }
+}
+
+/// Pre-trade limits. Any limit left unset is not enforced.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct RiskConfig {
+    pub max_order_quantity: Option<f64>,
+    pub max_order_notional: Option<f64>,
+    /// Maximum distance of a limit price from the last price, as a fraction.
+    pub price_collar_pct: Option<f64>,
+    pub max_position_per_symbol: Option<f64>,
+    pub max_open_orders: Option<usize>,
+    /// Loss since the start of the UTC day beyond which only risk-reducing orders are accepted.
+    pub daily_loss_limit: Option<f64>,
+}
+
+impl Default for RiskConfig {
+    fn default() -> Self {
+        Self {
+            max_order_quantity: Some(5.0),
+            max_order_notional: Some(250_000.0),
+            price_collar_pct: Some(0.05),
+            max_position_per_symbol: Some(10.0),
+            max_open_orders: Some(50),
+            daily_loss_limit: Some(10_000.0),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct SimulatorConfig {
+    /// Adverse price adjustment applied to market order fills, in basis points.
+    pub slippage_bps: f64,
+    /// Cap each fill at the size quoted or traded in the triggering update.
+    pub partial_fills: bool,
+}
+
+impl Default for SimulatorConfig {
+    fn default() -> Self {
+        Self {
+            slippage_bps: 1.0,
+            partial_fills: true,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+pub struct ControlConfig {
+    /// Address for the line-based control API, e.g. `127.0.0.1:9400`. Off
+    /// unless set.
+    pub listen_addr: Option<String>,
+    /// Shared secret `REARM` must carry. Unset means the kill switch can
+    /// only be re-armed from the UI; `KILL` never needs it.
+    #[serde(default)]
+    pub rearm_token: Option<String>,
+}
+
+/// Tees market data to gzip-compressed JSONL files for offline replay.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct RecorderConfig {
+    pub enabled: bool,
+    pub directory: String,
+    /// Symbols to record; empty records everything.
+    pub symbols: Vec<String>,
+    /// Record raw WebSocket frames as received.
+    pub raw_frames: bool,
+    /// Record normalized `MarketDataUpdate`s.
+    pub updates: bool,
+    /// Start a new file after this many minutes...
+    pub rotate_minutes: u64,
+    /// ...or this many uncompressed megabytes, whichever comes first.
+    pub rotate_megabytes: u64,
+}
+
+impl Default for RecorderConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            directory: "recordings".to_string(),
+            symbols: Vec::new(),
+            raw_frames: true,
+            updates: true,
+            rotate_minutes: 60,
+            rotate_megabytes: 256,
+        }
+    }
+}
+
+/// Plays recorded market data instead of connecting to the venues.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct ReplayConfig {
+    pub enabled: bool,
+    /// A recording directory or a single data file, as accepted by the backtester.
+    pub path: String,
+    /// Multiple of the recorded pace; 1.0 is real time, 0 replays as fast as possible.
+    pub speed: f64,
+    pub start_paused: bool,
+}
+
+impl Default for ReplayConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            path: "recordings".to_string(),
+            speed: 1.0,
+            start_paused: false,
+        }
+    }
+}
+
+/// How trades are grouped into candles. Written as `1s`, `5m`, `1h`, `1d`
+/// for time bars, or `tick:100`, `volume:25`, `dollar:1000000` for bars that
+/// close after that many trades, that much base quantity or that much
+/// notional.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Serialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum BarSpec {
+    /// Buckets of this many seconds, aligned to the epoch.
+    Time(u64),
+    Tick(u64),
+    Volume(f64),
+    Dollar(f64),
+}
+
+impl BarSpec {
+    pub fn is_time(&self) -> bool {
+        matches!(self, BarSpec::Time(_))
+    }
+}
+
+// Thresholds are never NaN; parsing rejects anything but positive numbers.
+impl Eq for BarSpec {}
+
+impl std::hash::Hash for BarSpec {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        std::mem::discriminant(self).hash(state);
+        match self {
+            BarSpec::Time(n) | BarSpec::Tick(n) => n.hash(state),
+            BarSpec::Volume(x) | BarSpec::Dollar(x) => x.to_bits().hash(state),
+        }
+    }
+}
+
+impl std::str::FromStr for BarSpec {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let s = s.trim();
+        if let Some((kind, threshold)) = s.split_once(':') {
+            let spec = match kind {
+                "tick" => BarSpec::Tick(threshold.parse()?),
+                "volume" => BarSpec::Volume(threshold.parse()?),
+                "dollar" => BarSpec::Dollar(threshold.parse()?),
+                other => anyhow::bail!("unknown bar type '{}'", other),
+            };
+            let positive = match spec {
+                BarSpec::Tick(n) => n > 0,
+                BarSpec::Volume(x) | BarSpec::Dollar(x) => x.is_finite() && x > 0.0,
+                BarSpec::Time(_) => unreachable!(),
+            };
+            anyhow::ensure!(positive, "bar threshold must be positive in '{}'", s);
+            return Ok(spec);
+        }
+
+        let split = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+        let (count, unit) = s.split_at(split);
+        let count: u64 = count.parse().map_err(|_| anyhow::anyhow!("invalid bar interval '{}'", s))?;
+        let unit_secs = match unit {
+            "s" => 1,
+            "m" => 60,
+            "h" => 3_600,
+            "d" => 86_400,
+            _ => anyhow::bail!("invalid bar interval '{}', expected a unit of s, m, h or d", s),
+        };
+        anyhow::ensure!(count > 0, "bar interval must be positive in '{}'", s);
+        // The aggregator turns this into a `chrono::Duration`, so it must fit one.
+        let secs = count
+            .checked_mul(unit_secs)
+            .filter(|secs| i64::try_from(*secs).ok().and_then(chrono::Duration::try_seconds).is_some())
+            .ok_or_else(|| anyhow::anyhow!("bar interval '{}' is too long", s))?;
+        Ok(BarSpec::Time(secs))
+    }
+}
+
+impl TryFrom<String> for BarSpec {
+    type Error = anyhow::Error;
+
+    fn try_from(s: String) -> Result<Self> {
+        s.parse()
+    }
+}
+
+impl From<BarSpec> for String {
+    fn from(spec: BarSpec) -> Self {
+        spec.to_string()
+    }
+}
+
+impl fmt::Display for BarSpec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            BarSpec::Time(secs) if secs % 86_400 == 0 => write!(f, "{}d", secs / 86_400),
+            BarSpec::Time(secs) if secs % 3_600 == 0 => write!(f, "{}h", secs / 3_600),
+            BarSpec::Time(secs) if secs % 60 == 0 => write!(f, "{}m", secs / 60),
+            BarSpec::Time(secs) => write!(f, "{}s", secs),
+            BarSpec::Tick(n) => write!(f, "tick:{}", n),
+            BarSpec::Volume(x) => write!(f, "volume:{}", x),
+            BarSpec::Dollar(x) => write!(f, "dollar:{}", x),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct CandleConfig {
+    pub bars: Vec<BarSpec>,
+    /// How far behind the newest trade a trade may be and still count
+    /// towards its time bar. Time bars close once trades this much past
+    /// their end have been seen; later stragglers are dropped.
+    pub allowed_lateness_ms: u64,
+}
+
+impl Default for CandleConfig {
+    fn default() -> Self {
+        Self {
+            bars: vec![BarSpec::Time(1), BarSpec::Time(60), BarSpec::Time(300), BarSpec::Time(3_600)],
+            allowed_lateness_ms: 1_000,
+        }
+    }
 }
 
 #[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct AppConfig {
     pub market_data: MarketDataConfig,
     pub strategy: StrategyConfig,
+    #[serde(default)]
+    pub oms: OmsConfig,
+    #[serde(default)]
+    pub control: ControlConfig,
+    #[serde(default)]
+    pub recorder: RecorderConfig,
+    #[serde(default)]
+    pub replay: ReplayConfig,
+    #[serde(default)]
+    pub candles: CandleConfig,
 }
 
+const CONFIG_FILE: &str = "config.toml";
+
 impl AppConfig {
     pub fn load() -> Result<Self> {
-        let config_path = Path::new("Config.toml");
+        let config_path = Path::new(CONFIG_FILE);
         if !config_path.exists() {
             let default_config = Self::default();
             let toml_string = toml::to_string_pretty(&default_config)?;
             std::fs::write(config_path, toml_string)?;
-            tracing::warn!("Config.toml not found, created a default one. Please review and restart.");
+            tracing::warn!("{} not found, created a default one. Please review and restart.", CONFIG_FILE);
             return Ok(default_config);
         }
 
         let settings = Config::builder()
-            .add_source(File::new("Config.toml", FileFormat::Toml))
+            .add_source(File::new(CONFIG_FILE, FileFormat::Toml))
             .build()?;
 
         let app_config: AppConfig = settings.try_deserialize()?;
@@ -45,14 +465,63 @@ impl AppConfig {
     pub fn default() -> Self {
         Self {
             market_data: MarketDataConfig {
-                websocket_url: "wss://stream.binance.com:9443/ws/btcusdt@trade".to_string(), 
-                symbols: vec!["BTCUSD".to_string(), "ETHUSD".to_string()],
+                websocket_url: "wss://stream.binance.com:9443".to_string(),
+                symbols: vec!["BTCUSDT".to_string(), "ETHUSDT".to_string()],
+                streams: default_streams(),
+                kline_interval: default_kline_interval(),
+                reconnect: ReconnectConfig::default(),
+                order_book: OrderBookConfig::default(),
+                symbol_venues: HashMap::new(),
+                coinbase: CoinbaseConfig::default(),
             },
             strategy: StrategyConfig {
                 enabled: true,
                 name: "MeanReversion".to_string(),
                 mean_reversion_threshold: 0.005, 
+                window_length: default_window_length(),
+                mean_type: MeanType::default(),
+                deviation_mode: DeviationMode::default(),
+                order_quantity: default_order_quantity(),
+                max_position: default_max_position(),
             },
+            oms: OmsConfig::default(),
+            control: ControlConfig::default(),
+            recorder: RecorderConfig::default(),
+            replay: ReplayConfig::default(),
+            candles: CandleConfig::default(),
         }
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn oms_journal_is_on_by_default_and_can_be_disabled() {
+        let config: OmsConfig = toml::from_str("").unwrap();
+        assert!(config.journal_enabled);
+        assert_eq!(config.journal_path, "oms_journal.jsonl");
+
+        let config: OmsConfig = toml::from_str("journal_enabled = false").unwrap();
+        assert!(!config.journal_enabled);
+    }
+
+    #[test]
+    fn control_api_is_off_unless_an_address_is_set() {
+        assert!(AppConfig::default().control.listen_addr.is_none());
+        let config: ControlConfig = toml::from_str("listen_addr = \"127.0.0.1:9400\"").unwrap();
+        assert_eq!(config.listen_addr.as_deref(), Some("127.0.0.1:9400"));
+    }
+
+    #[test]
+    fn bar_intervals_that_overflow_are_rejected() {
+        assert_eq!("90m".parse::<BarSpec>().unwrap(), BarSpec::Time(5_400));
+        assert_eq!("1d".parse::<BarSpec>().unwrap(), BarSpec::Time(86_400));
+
+        let overflow = format!("{}d", u64::MAX / 86_400 + 1);
+        let err = overflow.parse::<BarSpec>().unwrap_err().to_string();
+        assert!(err.contains("too long"), "{}", err);
+        assert!(format!("{}s", u64::MAX).parse::<BarSpec>().is_err());
+        assert!(format!("{}s", i64::MAX).parse::<BarSpec>().is_err());
+    }
+}
@@ -0,0 +1,205 @@
+use anyhow::{anyhow, Result};
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::TcpListener;
+use tokio::sync::mpsc;
+use tracing::{error, info, warn};
+
+use crate::config::ControlConfig;
+use crate::oms::OmsCommand;
+use crate::strategy::StrategyControl;
+
+/// Longest command line accepted; a client sending more is disconnected.
+const MAX_LINE_BYTES: u64 = 1024;
+
+/// Entry point for operator actions that span subsystems, shared by the UI
+/// and the control server.
+#[derive(Clone)]
+pub struct ControlHandle {
+    oms_tx: mpsc::UnboundedSender<OmsCommand>,
+    strategy_tx: mpsc::UnboundedSender<StrategyControl>,
+}
+
+impl ControlHandle {
+    pub fn new(
+        oms_tx: mpsc::UnboundedSender<OmsCommand>,
+        strategy_tx: mpsc::UnboundedSender<StrategyControl>,
+    ) -> Self {
+        Self { oms_tx, strategy_tx }
+    }
+
+    /// Stops all strategies, then has the OMS cancel every working order and
+    /// refuse new ones until re-armed.
+    pub fn engage_kill_switch(&self, triggered_by: &str, reason: &str) -> Result<()> {
+        warn!(target: "audit", "kill switch triggered by {}: {}", triggered_by, reason);
+        self.strategy_tx
+            .send(StrategyControl::Stop)
+            .map_err(|e| anyhow!("failed to stop strategies: {}", e))?;
+        self.oms_tx
+            .send(OmsCommand::KillSwitch {
+                triggered_by: triggered_by.to_string(),
+                reason: reason.to_string(),
+            })
+            .map_err(|e| anyhow!("failed to reach OMS: {}", e))
+    }
+
+    /// Allows new orders again. Strategies stay stopped until started explicitly.
+    pub fn rearm(&self, triggered_by: &str) -> Result<()> {
+        warn!(target: "audit", "kill switch re-arm requested by {}", triggered_by);
+        self.oms_tx
+            .send(OmsCommand::Rearm { triggered_by: triggered_by.to_string() })
+            .map_err(|e| anyhow!("failed to reach OMS: {}", e))
+    }
+}
+
+/// Operator identity attached to audit records: the OS user plus the channel
+/// the action came from.
+pub fn operator(channel: &str) -> String {
+    let user = std::env::var("USER")
+        .or_else(|_| std::env::var("USERNAME"))
+        .unwrap_or_else(|_| "unknown".to_string());
+    format!("{}@{}", user, channel)
+}
+
+/// Line-based TCP control API. Commands:
+/// `KILL <operator> [reason...]`, `REARM <operator> <token>`, `PING`.
+/// Anyone who can connect may stop trading; only holders of `rearm_token`
+/// may resume it. Lines longer than `MAX_LINE_BYTES` close the connection.
+pub async fn run_control_server(config: ControlConfig, handle: ControlHandle) -> Result<()> {
+    let listen_addr = config.listen_addr.ok_or_else(|| anyhow!("control API has no listen address"))?;
+    let listener = TcpListener::bind(&listen_addr).await?;
+    info!("Control API listening on {}", listen_addr);
+    if config.rearm_token.is_none() {
+        info!("Control API: no rearm_token set, REARM is disabled.");
+    }
+    let rearm_token = std::sync::Arc::new(config.rearm_token);
+
+    loop {
+        let (stream, peer) = listener.accept().await?;
+        let handle = handle.clone();
+        let rearm_token = rearm_token.clone();
+        tokio::spawn(async move {
+            let (read, mut write) = stream.into_split();
+            let mut reader = BufReader::new(read);
+            let mut buf = Vec::new();
+            loop {
+                buf.clear();
+                match (&mut reader).take(MAX_LINE_BYTES + 1).read_until(b'\n', &mut buf).await {
+                    Ok(0) | Err(_) => break,
+                    Ok(_) => {}
+                }
+                if !buf.ends_with(b"\n") && buf.len() as u64 > MAX_LINE_BYTES {
+                    warn!("Control API: line from {} exceeds {} bytes, disconnecting", peer, MAX_LINE_BYTES);
+                    let _ = write.write_all(b"ERR line too long\n").await;
+                    break;
+                }
+                let line = String::from_utf8_lossy(&buf);
+                let reply = match handle_command(&handle, &line, &peer.to_string(), rearm_token.as_deref()) {
+                    Ok(msg) => format!("OK {}\n", msg),
+                    Err(e) => format!("ERR {}\n", e),
+                };
+                if let Err(e) = write.write_all(reply.as_bytes()).await {
+                    error!("Control API write to {} failed: {}", peer, e);
+                    break;
+                }
+            }
+        });
+    }
+}
+
+fn handle_command(handle: &ControlHandle, line: &str, peer: &str, rearm_token: Option<&str>) -> Result<String> {
+    let mut parts = line.split_whitespace();
+    let command = parts.next().unwrap_or_default().to_ascii_uppercase();
+    match command.as_str() {
+        "PING" => Ok("PONG".to_string()),
+        "KILL" => {
+            let who = parts.next().ok_or_else(|| anyhow!("usage: KILL <operator> [reason]"))?;
+            let reason: Vec<&str> = parts.collect();
+            let reason = if reason.is_empty() { "no reason given".to_string() } else { reason.join(" ") };
+            handle.engage_kill_switch(&format!("{}@control-api({})", who, peer), &reason)?;
+            Ok("kill switch engaged".to_string())
+        }
+        "REARM" => {
+            let expected = rearm_token.ok_or_else(|| anyhow!("re-arming over the control API is disabled"))?;
+            let (Some(who), Some(token)) = (parts.next(), parts.next()) else {
+                return Err(anyhow!("usage: REARM <operator> <token>"));
+            };
+            if token != expected {
+                warn!(target: "audit", "rejected REARM from {} ({}): bad token", who, peer);
+                return Err(anyhow!("invalid token"));
+            }
+            handle.rearm(&format!("{}@control-api({})", who, peer))?;
+            Ok("re-armed".to_string())
+        }
+        other => Err(anyhow!("unknown command '{}'", other)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn handle() -> (ControlHandle, mpsc::UnboundedReceiver<OmsCommand>, mpsc::UnboundedReceiver<StrategyControl>) {
+        let (oms_tx, oms_rx) = mpsc::unbounded_channel();
+        let (strategy_tx, strategy_rx) = mpsc::unbounded_channel();
+        (ControlHandle::new(oms_tx, strategy_tx), oms_rx, strategy_rx)
+    }
+
+    #[test]
+    fn kill_stops_strategies_and_engages_the_oms() {
+        let (handle, mut oms_rx, mut strategy_rx) = handle();
+        assert_eq!(handle_command(&handle, "kill alice fat finger", "peer", None).unwrap(), "kill switch engaged");
+        assert!(matches!(strategy_rx.try_recv(), Ok(StrategyControl::Stop)));
+        match oms_rx.try_recv() {
+            Ok(OmsCommand::KillSwitch { triggered_by, reason }) => {
+                assert_eq!(triggered_by, "alice@control-api(peer)");
+                assert_eq!(reason, "fat finger");
+            }
+            other => panic!("expected a kill switch command, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn rearm_needs_the_configured_token() {
+        let (handle, mut oms_rx, _strategy_rx) = handle();
+        assert!(handle_command(&handle, "REARM alice secret", "peer", None).is_err());
+        assert!(handle_command(&handle, "REARM alice", "peer", Some("secret")).is_err());
+        let err = handle_command(&handle, "REARM alice guess", "peer", Some("secret")).unwrap_err();
+        assert_eq!(err.to_string(), "invalid token");
+        assert!(oms_rx.try_recv().is_err());
+
+        assert_eq!(handle_command(&handle, "REARM alice secret", "peer", Some("secret")).unwrap(), "re-armed");
+        assert!(matches!(oms_rx.try_recv(), Ok(OmsCommand::Rearm { triggered_by }) if triggered_by == "alice@control-api(peer)"));
+    }
+
+    #[tokio::test]
+    async fn overlong_lines_close_the_connection() {
+        use tokio::net::TcpStream;
+
+        let addr = std::net::TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap();
+        let config = ControlConfig { listen_addr: Some(addr.to_string()), rearm_token: None };
+        let (handle, _oms_rx, _strategy_rx) = handle();
+        tokio::spawn(run_control_server(config, handle));
+
+        let mut stream = loop {
+            match TcpStream::connect(addr).await {
+                Ok(stream) => break stream,
+                Err(_) => tokio::time::sleep(tokio::time::Duration::from_millis(10)).await,
+            }
+        };
+        stream.write_all(b"PING\n").await.unwrap();
+        stream.write_all(&vec![b'A'; MAX_LINE_BYTES as usize + 10]).await.unwrap();
+        let mut replies = BufReader::new(stream).lines();
+        assert_eq!(replies.next_line().await.unwrap().as_deref(), Some("OK PONG"));
+        assert_eq!(replies.next_line().await.unwrap().as_deref(), Some("ERR line too long"));
+        assert_eq!(replies.next_line().await.unwrap(), None);
+    }
+
+    #[test]
+    fn ping_and_unknown_commands() {
+        let (handle, mut oms_rx, _strategy_rx) = handle();
+        assert_eq!(handle_command(&handle, "ping", "peer", None).unwrap(), "PONG");
+        assert_eq!(handle_command(&handle, "FLATTEN all", "peer", None).unwrap_err().to_string(), "unknown command 'FLATTEN'");
+        assert!(handle_command(&handle, "KILL", "peer", None).is_err());
+        assert!(oms_rx.try_recv().is_err());
+    }
+}
@@ -2,6 +2,9 @@ use serde::{Deserialize, Serialize};
 use chrono::{DateTime, Utc};
 use uuid::Uuid;
 
+/// Quantities within this of zero are treated as zero.
+pub const QTY_EPSILON: f64 = 1e-12;
+
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum Side {
     Buy,
@@ -14,6 +17,7 @@ pub enum OrderType {
     Limit,
 }
 
+#[allow(clippy::upper_case_acronyms)]
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum TimeInForce {
     GTC, 
@@ -30,6 +34,9 @@ pub struct MarketDataUpdate {
     pub ask_quantity: Option<f64>,
     pub last_price: Option<f64>,
     pub last_quantity: Option<f64>,
+    /// Taker side of a trade, when the venue reports it.
+    #[serde(default)]
+    pub aggressor: Option<Side>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -66,7 +73,7 @@ impl Order {
         tif: TimeInForce,
     ) -> Self {
         let order_id = Uuid::new_v4();
-        let client_order_id = format!("cl_{}", order_id.to_string()); 
+        let client_order_id = format!("cl_{}", order_id); 
         Self {
             order_id,
             client_order_id,
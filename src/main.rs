@@ -1,35 +1,172 @@
 mod app;
+mod backtest;
 mod config;
+mod control;
 mod data_model;
 mod market_data;
 mod oms;
 mod strategy;
+#[cfg(test)]
+mod test_support;
 mod utils;
 
-use app::AlgoApp;
+use app::{AlgoApp, AppChannels};
+use backtest::BacktestConfig;
+use config::AppConfig;
+use market_data::replay::ReplayHandle;
+use std::path::{Path, PathBuf};
 use tokio::sync::mpsc;
-use tracing::info;
+use tracing::{error, info};
 
-#[tokio::main]
-async fn main() -> anyhow::Result<()> {
+fn main() -> anyhow::Result<()> {
     utils::init_logging()?;
-    info!("Application starting (UI only at this stage)...");
+    info!("Application starting...");
 
-    let (_md_tx, md_rx) = mpsc::unbounded_channel(); 
-    let (_oms_tx, oms_rx) = mpsc::unbounded_channel(); 
-    let (ui_order_tx, _ui_order_rx) = mpsc::unbounded_channel(); 
-    let (ui_strategy_tx, _ui_strategy_rx) = mpsc::unbounded_channel(); 
+    let mut config = AppConfig::load()?;
 
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match args.first().map(String::as_str) {
+        Some("backtest") => return run_backtest_mode(&args[1..], &config),
+        Some("replay") => apply_replay_args(&args[1..], &mut config.replay)?,
+        _ => {}
+    }
+
+    let runtime = tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .thread_name("analyase-worker")
+        .build()?;
+
+    let (md_tx, md_rx) = mpsc::unbounded_channel();
+    let (ui_md_tx, ui_md_rx) = mpsc::unbounded_channel();
+    let (oms_md_tx, oms_md_rx) = mpsc::unbounded_channel();
+    let (strategy_md_tx, strategy_md_rx) = mpsc::unbounded_channel();
+    let (candle_md_tx, candle_md_rx) = mpsc::unbounded_channel();
+    let (candle_tx, candle_rx) = mpsc::unbounded_channel();
+    let (ui_candle_tx, ui_candle_rx) = mpsc::unbounded_channel();
+    let (strategy_candle_tx, strategy_candle_rx) = mpsc::unbounded_channel();
+    let (md_status_tx, md_status_rx) = mpsc::unbounded_channel();
+    let (md_command_tx, md_command_rx) = mpsc::unbounded_channel();
+    let (oms_tx, oms_rx) = mpsc::unbounded_channel();
+    let (ui_oms_tx, ui_oms_rx) = mpsc::unbounded_channel();
+    let (strategy_oms_tx, strategy_oms_rx) = mpsc::unbounded_channel();
+    let (ui_order_tx, ui_order_rx) = mpsc::unbounded_channel();
+    let (ui_strategy_tx, ui_strategy_rx) = mpsc::unbounded_channel();
+
+    let order_books = market_data::OrderBooks::new();
+    let mut symbols = config.market_data.symbols.clone();
+    let mut replay = None;
+
+    let replaying = config.replay.enabled;
+    if replaying {
+        // Simulated fills from recorded data must never reach the live journal.
+        if std::mem::take(&mut config.oms.journal_enabled) {
+            info!("Replay mode: OMS journal disabled.");
+        }
+        let updates = backtest::loader::load_market_data(Path::new(&config.replay.path))?;
+        symbols.clear();
+        for update in &updates {
+            market_data::MarketDataCommand::Subscribe(update.symbol.clone()).apply(&mut symbols);
+        }
+        let (replay_command_tx, replay_command_rx) = mpsc::unbounded_channel();
+        let (replay_status_tx, replay_status_rx) = mpsc::unbounded_channel();
+        replay = Some(ReplayHandle { command_tx: replay_command_tx, status_rx: replay_status_rx });
+        runtime.spawn(async move {
+            if let Err(e) = market_data::replay::run_replay(
+                config.replay,
+                updates,
+                md_tx,
+                md_command_rx,
+                replay_command_rx,
+                replay_status_tx,
+            )
+            .await
+            {
+                error!("Market data replay exited with error: {}", e);
+            }
+        });
+    } else {
+        let recorder = if config.recorder.enabled {
+            let (handle, record_rx) = market_data::RecorderHandle::channel();
+            runtime.spawn(async move {
+                if let Err(e) = market_data::recorder::run_recorder(config.recorder, record_rx).await {
+                    error!("Market data recorder exited with error: {:#}", e);
+                }
+            });
+            handle
+        } else {
+            market_data::RecorderHandle::disabled()
+        };
+
+        let sinks = market_data::FeedSinks { books: order_books.clone(), md_tx, recorder };
+        runtime.spawn(async move {
+            if let Err(e) = market_data::run_market_data_handler(config.market_data, sinks, md_command_rx, md_status_tx).await {
+                error!("Market data handler exited with error: {}", e);
+            }
+        });
+    }
+
+    runtime.spawn(utils::fan_out(md_rx, vec![ui_md_tx, oms_md_tx, strategy_md_tx, candle_md_tx]));
+    runtime.spawn(utils::fan_out(candle_rx, vec![ui_candle_tx, strategy_candle_tx]));
+    runtime.spawn(utils::fan_out(oms_rx, vec![ui_oms_tx, strategy_oms_tx]));
+
+    runtime.spawn(async move {
+        if let Err(e) = market_data::candles::run_candle_aggregator(config.candles, candle_md_rx, candle_tx, !replaying).await {
+            error!("Candle aggregator exited with error: {}", e);
+        }
+    });
+
+    runtime.spawn(async move {
+        if let Err(e) = oms::run_oms(config.oms, ui_order_rx, oms_md_rx, oms_tx).await {
+            error!("OMS exited with error: {}", e);
+        }
+    });
+
+    let control = control::ControlHandle::new(ui_order_tx.clone(), ui_strategy_tx.clone());
+    if config.control.listen_addr.is_some() {
+        let control = control.clone();
+        let control_config = config.control.clone();
+        runtime.spawn(async move {
+            if let Err(e) = control::run_control_server(control_config, control).await {
+                error!("Control API exited with error: {}", e);
+            }
+        });
+    }
+
+    let strategy_enabled = config.strategy.enabled;
+    let strategy_order_tx = ui_order_tx.clone();
+    let strategy_books = order_books.clone();
+    runtime.spawn(async move {
+        let registry = strategy::StrategyRegistry::with_builtin();
+        let channels = strategy::EngineChannels {
+            control_rx: ui_strategy_rx,
+            md_rx: strategy_md_rx,
+            candle_rx: strategy_candle_rx,
+            oms_rx: strategy_oms_rx,
+            order_tx: strategy_order_tx,
+        };
+        if let Err(e) = strategy::run_strategy_engine(config.strategy, registry, channels, strategy_books).await {
+            error!("Strategy engine exited with error: {}", e);
+        }
+    });
 
     let native_options = eframe::NativeOptions {
         viewport: egui::ViewportBuilder::default()
             .with_inner_size([1200.0, 800.0])
             .with_min_inner_size([800.0, 600.0]),
-          
         ..Default::default()
     };
 
-    let app_state = AlgoApp::new(md_rx, oms_rx, ui_order_tx, ui_strategy_tx);
+    let channels = AppChannels {
+        market_data_rx: ui_md_rx,
+        candle_rx: ui_candle_rx,
+        md_status_rx,
+        oms_rx: ui_oms_rx,
+        order_tx: ui_order_tx,
+        strategy_tx: ui_strategy_tx,
+        md_command_tx,
+        replay,
+    };
+    let app_state = AlgoApp::new(channels, control, order_books, symbols, strategy_enabled);
 
     eframe::run_native(
         "Analyase",
@@ -38,7 +175,59 @@ async fn main() -> anyhow::Result<()> {
     )
     .map_err(|e| anyhow::anyhow!("eframe error: {}", e))?;
 
+    runtime.shutdown_timeout(std::time::Duration::from_secs(2));
     info!("Application shut down.");
 
     Ok(())
-}
\ No newline at end of file
+}
+
+/// `analyase backtest <data.csv|data.jsonl> [--capital N] [--equity-out path]`
+fn run_backtest_mode(args: &[String], config: &AppConfig) -> anyhow::Result<()> {
+    let mut data_path = None;
+    let mut initial_capital = 100_000.0;
+    let mut equity_out = None;
+
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--capital" => {
+                let value = iter.next().ok_or_else(|| anyhow::anyhow!("--capital requires a value"))?;
+                initial_capital = value.parse()?;
+            }
+            "--equity-out" => {
+                let value = iter.next().ok_or_else(|| anyhow::anyhow!("--equity-out requires a path"))?;
+                equity_out = Some(PathBuf::from(value));
+            }
+            other => data_path = Some(PathBuf::from(other)),
+        }
+    }
+
+    let backtest_config = BacktestConfig {
+        data_path: data_path.ok_or_else(|| anyhow::anyhow!("usage: analyase backtest <data file> [--capital N] [--equity-out path]"))?,
+        initial_capital,
+        equity_out,
+    };
+
+    let registry = strategy::StrategyRegistry::with_builtin();
+    let report = backtest::run_backtest(&backtest_config, config, &registry)?;
+    println!("{}", report);
+    Ok(())
+}
+
+/// `analyase replay <path> [--speed N] [--paused]` runs the app on recorded
+/// data instead of the live feeds.
+fn apply_replay_args(args: &[String], replay: &mut config::ReplayConfig) -> anyhow::Result<()> {
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--speed" => {
+                let value = iter.next().ok_or_else(|| anyhow::anyhow!("--speed requires a value"))?;
+                replay.speed = value.parse()?;
+            }
+            "--paused" => replay.start_paused = true,
+            other => replay.path = other.to_string(),
+        }
+    }
+    replay.enabled = true;
+    Ok(())
+}
@@ -0,0 +1,355 @@
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Utc};
+use futures_util::future::BoxFuture;
+use futures_util::{SinkExt, StreamExt};
+use serde_json::Value;
+use tracing::debug;
+use tungstenite::Message;
+use url::Url;
+
+use crate::config::{MarketDataConfig, StreamKind, Venue};
+use crate::data_model::{Candlestick, MarketDataUpdate, Side};
+use crate::market_data::order_book::{DepthDiff, DepthSnapshot, PriceLevel};
+use crate::market_data::source::{number_field, str_field, MarketDataSource, MarketEvent, ParseError};
+
+/// Binance spot streams over the combined-stream endpoint, with depth
+/// snapshots from the WebSocket API.
+pub struct BinanceSource {
+    config: MarketDataConfig,
+}
+
+impl BinanceSource {
+    pub fn new(config: MarketDataConfig) -> Self {
+        Self { config }
+    }
+
+    /// Stream names for one symbol, e.g. `btcusdt@trade`.
+    pub fn stream_names(&self, symbol: &str) -> Vec<String> {
+        let symbol = symbol.to_lowercase();
+        let mut names: Vec<String> = self
+            .config
+            .streams
+            .iter()
+            .map(|kind| match kind {
+                StreamKind::Trade => format!("{}@trade", symbol),
+                StreamKind::AggTrade => format!("{}@aggTrade", symbol),
+                StreamKind::BookTicker => format!("{}@bookTicker", symbol),
+                StreamKind::Kline => format!("{}@kline_{}", symbol, self.config.kline_interval),
+            })
+            .collect();
+        if self.config.order_book.enabled {
+            names.push(format!("{}@depth@100ms", symbol));
+        }
+        names
+    }
+}
+
+impl MarketDataSource for BinanceSource {
+    fn venue(&self) -> Venue {
+        Venue::Binance
+    }
+
+    /// `<endpoint>/stream?streams=a/b/c` for every symbol's streams. Any path
+    /// in the configured URL (such as a legacy `/ws/<stream>`) is replaced.
+    fn connect_url(&self, symbols: &[String]) -> Result<Url> {
+        let mut url = Url::parse(&self.config.websocket_url)?;
+        let streams: Vec<String> = symbols.iter().flat_map(|s| self.stream_names(s)).collect();
+        url.set_path("/stream");
+        url.set_query((!streams.is_empty()).then(|| format!("streams={}", streams.join("/"))).as_deref());
+        Ok(url)
+    }
+
+    fn subscription_frame(&self, symbol: &str, subscribe: bool, request_id: u64) -> String {
+        serde_json::json!({
+            "method": if subscribe { "SUBSCRIBE" } else { "UNSUBSCRIBE" },
+            "params": self.stream_names(symbol),
+            "id": request_id,
+        })
+        .to_string()
+    }
+
+    fn parse(&self, text: &str) -> Result<Vec<MarketEvent>, ParseError> {
+        parse_event(text)
+    }
+
+    fn depth_snapshot(&self, symbol: &str) -> Option<BoxFuture<'static, Result<DepthSnapshot>>> {
+        let book = &self.config.order_book;
+        if !book.enabled {
+            return None;
+        }
+        let (url, symbol, limit) = (book.snapshot_url.clone(), symbol.to_string(), book.snapshot_limit);
+        Some(Box::pin(async move { fetch_depth_snapshot(&url, &symbol, limit).await }))
+    }
+}
+
+/// Requests a depth snapshot over the Binance WebSocket API.
+pub async fn fetch_depth_snapshot(api_url: &str, symbol: &str, limit: u32) -> Result<DepthSnapshot> {
+    debug!("Requesting depth snapshot for {} from {}", symbol, api_url);
+    let (mut ws, _) = tokio_tungstenite::connect_async(Url::parse(api_url)?).await
+        .map_err(|e| anyhow!("Failed to connect to {}: {}", api_url, e))?;
+
+    let request = serde_json::json!({
+        "id": format!("depth-{}", symbol),
+        "method": "depth",
+        "params": { "symbol": symbol.to_uppercase(), "limit": limit },
+    });
+    ws.send(Message::Text(request.to_string())).await?;
+
+    while let Some(msg) = ws.next().await {
+        if let Message::Text(text) = msg? {
+            let _ = ws.close(None).await;
+            return Ok(parse_depth_snapshot(&text)?);
+        }
+    }
+    Err(anyhow!("Connection closed before depth snapshot for {} arrived", symbol))
+}
+
+pub fn parse_event(json_str: &str) -> Result<Vec<MarketEvent>, ParseError> {
+    let mut v: Value = serde_json::from_str(json_str)?;
+    // Subscription acks: {"result": null, "id": 1}.
+    if v.get("result").is_some() && v.get("id").is_some() {
+        return Ok(Vec::new());
+    }
+    // Combined streams wrap each payload as {"stream": .., "data": ..}.
+    if v.get("stream").is_some() {
+        if let Some(data) = v.get_mut("data") {
+            v = data.take();
+        }
+    }
+    let event = match v.get("e").and_then(Value::as_str) {
+        Some("trade") | Some("aggTrade") => MarketEvent::Update(parse_trade(&v)?),
+        Some("depthUpdate") => MarketEvent::Depth(parse_depth_update(&v)?),
+        Some("kline") => parse_kline(&v)?,
+        Some(other) => return Err(ParseError::UnsupportedEvent(other.to_string())),
+        // bookTicker is the only stream without an event type.
+        None if v.get("b").is_some() && v.get("a").is_some() => MarketEvent::Update(parse_book_ticker(&v)?),
+        None => return Err(ParseError::UnsupportedEvent("<none>".to_string())),
+    };
+    Ok(vec![event])
+}
+
+fn id_field(v: &Value, field: &'static str) -> Result<u64, ParseError> {
+    v.get(field).and_then(Value::as_u64).ok_or(ParseError::MissingField(field))
+}
+
+fn time_field(v: &Value, field: &'static str) -> Result<DateTime<Utc>, ParseError> {
+    let ms = v.get(field).and_then(Value::as_i64).ok_or(ParseError::MissingField(field))?;
+    DateTime::from_timestamp_millis(ms).ok_or(ParseError::InvalidTimestamp(ms.to_string()))
+}
+
+fn parse_trade(v: &Value) -> Result<MarketDataUpdate, ParseError> {
+    Ok(MarketDataUpdate {
+        symbol: str_field(v, "s")?.to_string(),
+        timestamp: time_field(v, "T").or_else(|_| time_field(v, "E"))?,
+        bid_price: None,
+        bid_quantity: None,
+        ask_price: None,
+        ask_quantity: None,
+        last_price: Some(number_field(v, "p")?),
+        last_quantity: Some(number_field(v, "q")?),
+        // `m` is set when the buyer was the maker, i.e. the seller hit the bid.
+        aggressor: v.get("m").and_then(Value::as_bool).map(|buyer_maker| if buyer_maker { Side::Sell } else { Side::Buy }),
+    })
+}
+
+fn parse_book_ticker(v: &Value) -> Result<MarketDataUpdate, ParseError> {
+    Ok(MarketDataUpdate {
+        symbol: str_field(v, "s")?.to_string(),
+        // Only the futures variant carries an event time.
+        timestamp: time_field(v, "E").unwrap_or_else(|_| Utc::now()),
+        bid_price: Some(number_field(v, "b")?),
+        bid_quantity: Some(number_field(v, "B")?),
+        ask_price: Some(number_field(v, "a")?),
+        ask_quantity: Some(number_field(v, "A")?),
+        last_price: None,
+        last_quantity: None,
+        aggressor: None,
+    })
+}
+
+fn parse_levels(v: &Value, field: &'static str) -> Result<Vec<PriceLevel>, ParseError> {
+    let levels = v.get(field).and_then(Value::as_array).ok_or(ParseError::MissingField(field))?;
+    levels
+        .iter()
+        .map(|level| {
+            let number = |i: usize| -> Result<f64, ParseError> {
+                let raw = level.get(i).and_then(Value::as_str).ok_or(ParseError::MissingField(field))?;
+                raw.parse().map_err(|_| ParseError::InvalidNumber { field, value: raw.to_string() })
+            };
+            Ok(PriceLevel { price: number(0)?, quantity: number(1)? })
+        })
+        .collect()
+}
+
+fn parse_depth_update(v: &Value) -> Result<DepthDiff, ParseError> {
+    Ok(DepthDiff {
+        symbol: str_field(v, "s")?.to_string(),
+        event_time: time_field(v, "E")?,
+        first_update_id: id_field(v, "U")?,
+        final_update_id: id_field(v, "u")?,
+        bids: parse_levels(v, "b")?,
+        asks: parse_levels(v, "a")?,
+    })
+}
+
+fn parse_kline(v: &Value) -> Result<MarketEvent, ParseError> {
+    let k = v.get("k").ok_or(ParseError::MissingField("k"))?;
+    Ok(MarketEvent::Candle {
+        symbol: str_field(v, "s")?.to_string(),
+        interval: str_field(k, "i")?.to_string(),
+        closed: k.get("x").and_then(Value::as_bool).ok_or(ParseError::MissingField("x"))?,
+        candle: Candlestick {
+            timestamp: time_field(k, "t")?,
+            open: number_field(k, "o")?,
+            high: number_field(k, "h")?,
+            low: number_field(k, "l")?,
+            close: number_field(k, "c")?,
+            volume: number_field(k, "v")?,
+        },
+    })
+}
+
+/// Parses a WebSocket API `depth` response.
+fn parse_depth_snapshot(json_str: &str) -> Result<DepthSnapshot, ParseError> {
+    let v: Value = serde_json::from_str(json_str)?;
+    if let Some(error) = v.get("error") {
+        return Err(ParseError::ErrorResponse(error.to_string()));
+    }
+    let result = v.get("result").ok_or(ParseError::MissingField("result"))?;
+    Ok(DepthSnapshot {
+        last_update_id: id_field(result, "lastUpdateId")?,
+        bids: parse_levels(result, "bids")?,
+        asks: parse_levels(result, "asks")?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TRADE: &str = include_str!("../../tests/fixtures/binance/trade.json");
+    const AGG_TRADE: &str = include_str!("../../tests/fixtures/binance/agg_trade.json");
+    const BOOK_TICKER: &str = include_str!("../../tests/fixtures/binance/book_ticker.json");
+    const DEPTH_UPDATE: &str = include_str!("../../tests/fixtures/binance/depth_update.json");
+    const DEPTH_SNAPSHOT: &str = include_str!("../../tests/fixtures/binance/depth_snapshot.json");
+    const DEPTH_ERROR: &str = include_str!("../../tests/fixtures/binance/depth_error.json");
+    const KLINE: &str = include_str!("../../tests/fixtures/binance/kline.json");
+    const SUBSCRIPTION_ACK: &str = include_str!("../../tests/fixtures/binance/subscription_ack.json");
+    const TRADE_MISSING_PRICE: &str = include_str!("../../tests/fixtures/binance/trade_missing_price.json");
+    const TRADE_BAD_QUANTITY: &str = include_str!("../../tests/fixtures/binance/trade_bad_quantity.json");
+    const UNKNOWN_STREAM: &str = include_str!("../../tests/fixtures/binance/unknown_stream.json");
+    const MALFORMED: &str = include_str!("../../tests/fixtures/binance/malformed.json");
+
+    fn update(frame: &str) -> MarketDataUpdate {
+        match parse_event(frame).unwrap().as_slice() {
+            [MarketEvent::Update(update)] => update.clone(),
+            other => panic!("expected one update, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn trade_maps_to_last_price_and_aggressor() {
+        let update = update(TRADE);
+        assert_eq!(update.symbol, "BTCUSDT");
+        assert_eq!(update.timestamp.timestamp_millis(), 1718000000099);
+        assert_eq!((update.last_price, update.last_quantity), (Some(67000.5), Some(0.25)));
+        assert_eq!(update.bid_price, None);
+        assert_eq!(update.aggressor, Some(Side::Sell));
+    }
+
+    #[test]
+    fn agg_trade_maps_like_a_trade() {
+        let update = update(AGG_TRADE);
+        assert_eq!(update.timestamp.timestamp_millis(), 1718000000299);
+        assert_eq!((update.last_price, update.last_quantity), (Some(67001.0), Some(0.1)));
+        assert_eq!(update.aggressor, Some(Side::Buy));
+    }
+
+    #[test]
+    fn book_ticker_maps_to_quote() {
+        let update = update(BOOK_TICKER);
+        assert_eq!((update.bid_price, update.bid_quantity), (Some(67000.1), Some(1.5)));
+        assert_eq!((update.ask_price, update.ask_quantity), (Some(67000.2), Some(2.0)));
+        assert_eq!(update.last_price, None);
+    }
+
+    #[test]
+    fn depth_update_maps_to_diff() {
+        let events = parse_event(DEPTH_UPDATE).unwrap();
+        let [MarketEvent::Depth(diff)] = events.as_slice() else {
+            panic!("expected one depth diff, got {:?}", events);
+        };
+        assert_eq!((diff.first_update_id, diff.final_update_id), (101, 103));
+        assert_eq!(diff.bids, vec![PriceLevel { price: 67000.0, quantity: 3.0 }]);
+        assert_eq!(diff.asks[0], PriceLevel { price: 67001.0, quantity: 0.0 });
+    }
+
+    #[test]
+    fn kline_maps_to_candlestick() {
+        let events = parse_event(KLINE).unwrap();
+        let [MarketEvent::Candle { symbol, interval, closed, candle }] = events.as_slice() else {
+            panic!("expected one candle, got {:?}", events);
+        };
+        assert_eq!((symbol.as_str(), interval.as_str(), *closed), ("BTCUSDT", "1m", true));
+        assert_eq!(candle.timestamp.timestamp_millis(), 1718000000000);
+        assert_eq!((candle.open, candle.high, candle.low, candle.close), (67000.0, 67020.0, 66990.0, 67010.0));
+        assert_eq!(candle.volume, 12.5);
+    }
+
+    #[test]
+    fn depth_snapshot_and_error_response() {
+        let snapshot = parse_depth_snapshot(DEPTH_SNAPSHOT).unwrap();
+        assert_eq!(snapshot.last_update_id, 100);
+        assert_eq!((snapshot.bids.len(), snapshot.asks.len()), (2, 2));
+        match parse_depth_snapshot(DEPTH_ERROR) {
+            Err(ParseError::ErrorResponse(msg)) => assert!(msg.contains("Invalid symbol")),
+            other => panic!("expected error response, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn subscription_ack_yields_no_events() {
+        assert!(parse_event(SUBSCRIPTION_ACK).unwrap().is_empty());
+    }
+
+    #[test]
+    fn typed_parse_errors() {
+        assert!(matches!(parse_event(TRADE_MISSING_PRICE), Err(ParseError::MissingField("p"))));
+        assert!(matches!(
+            parse_event(TRADE_BAD_QUANTITY),
+            Err(ParseError::InvalidNumber { field: "q", value }) if value == "abc"
+        ));
+        assert!(matches!(parse_event(UNKNOWN_STREAM), Err(ParseError::UnsupportedEvent(e)) if e == "24hrTicker"));
+        assert!(matches!(parse_event(MALFORMED), Err(ParseError::Json(_))));
+    }
+
+    fn source(url: &str, streams: Vec<StreamKind>, order_book: bool) -> BinanceSource {
+        let mut config = crate::config::AppConfig::default().market_data;
+        config.websocket_url = url.to_string();
+        config.streams = streams;
+        config.kline_interval = "5m".to_string();
+        config.order_book.enabled = order_book;
+        BinanceSource::new(config)
+    }
+
+    #[test]
+    fn connect_url_combines_every_symbols_streams() {
+        let source = source("wss://stream.binance.com:9443", vec![StreamKind::Trade, StreamKind::Kline], true);
+        let url = source.connect_url(&["BTCUSDT".to_string(), "ethusdt".to_string()]).unwrap();
+        assert_eq!(
+            url.as_str(),
+            "wss://stream.binance.com:9443/stream?streams=\
+             btcusdt@trade/btcusdt@kline_5m/btcusdt@depth@100ms/ethusdt@trade/ethusdt@kline_5m/ethusdt@depth@100ms"
+        );
+    }
+
+    #[test]
+    fn connect_url_replaces_a_legacy_path() {
+        let source = source("wss://stream.binance.com:9443/ws/btcusdt@trade", vec![StreamKind::BookTicker], false);
+        let url = source.connect_url(&["BTCUSDT".to_string()]).unwrap();
+        assert_eq!(url.as_str(), "wss://stream.binance.com:9443/stream?streams=btcusdt@bookTicker");
+
+        let url = source.connect_url(&[]).unwrap();
+        assert_eq!(url.as_str(), "wss://stream.binance.com:9443/stream");
+    }
+}
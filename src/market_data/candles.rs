@@ -0,0 +1,355 @@
+use std::collections::{BTreeMap, HashMap};
+
+use anyhow::Result;
+use chrono::{DateTime, Duration, Utc};
+use tokio::sync::mpsc;
+use tracing::{debug, info};
+
+use crate::config::{BarSpec, CandleConfig};
+use crate::data_model::{Candlestick, MarketDataUpdate, QTY_EPSILON};
+
+/// A bar that changed. In-progress bars are republished on every trade that
+/// touches them; `closed` is set exactly once per bar, after which it never
+/// changes again.
+#[derive(Debug, Clone)]
+pub struct CandleUpdate {
+    pub symbol: String,
+    pub spec: BarSpec,
+    pub candle: Candlestick,
+    pub closed: bool,
+}
+
+/// A bar plus the times of the trades that set its open and close, so
+/// out-of-order trades land on the right side.
+#[derive(Debug, Clone)]
+struct Bar {
+    candle: Candlestick,
+    first_trade: DateTime<Utc>,
+    last_trade: DateTime<Utc>,
+}
+
+impl Bar {
+    fn new(start: DateTime<Utc>, time: DateTime<Utc>, price: f64, quantity: f64) -> Self {
+        Self {
+            candle: Candlestick { timestamp: start, open: price, high: price, low: price, close: price, volume: quantity },
+            first_trade: time,
+            last_trade: time,
+        }
+    }
+
+    fn add(&mut self, time: DateTime<Utc>, price: f64, quantity: f64) {
+        let candle = &mut self.candle;
+        candle.high = candle.high.max(price);
+        candle.low = candle.low.min(price);
+        candle.volume += quantity;
+        if time < self.first_trade {
+            self.first_trade = time;
+            candle.open = price;
+        }
+        if time >= self.last_trade {
+            self.last_trade = time;
+            candle.close = price;
+        }
+    }
+
+    /// Like `add`, but the trade is taken as the latest whatever its time.
+    fn append(&mut self, time: DateTime<Utc>, price: f64, quantity: f64) {
+        let candle = &mut self.candle;
+        candle.high = candle.high.max(price);
+        candle.low = candle.low.min(price);
+        candle.volume += quantity;
+        candle.close = price;
+        self.last_trade = time;
+    }
+}
+
+enum Series {
+    /// Open buckets by start time. Every bucket starting before
+    /// `closed_before` has been published as closed.
+    Time {
+        length: Duration,
+        open: BTreeMap<DateTime<Utc>, Bar>,
+        newest_trade: Option<DateTime<Utc>>,
+        closed_before: Option<DateTime<Utc>>,
+    },
+    /// Tick, volume and dollar bars take trades in arrival order and are
+    /// stamped with their first trade's time.
+    Activity { current: Option<Bar>, progress: f64 },
+}
+
+/// Builds candles for every configured bar type from a trade stream.
+pub struct CandleAggregator {
+    specs: Vec<BarSpec>,
+    lateness: Duration,
+    series: HashMap<(String, BarSpec), Series>,
+    late_trades: u64,
+}
+
+impl CandleAggregator {
+    pub fn new(config: &CandleConfig) -> Self {
+        Self {
+            specs: config.bars.clone(),
+            lateness: Duration::milliseconds(config.allowed_lateness_ms as i64),
+            series: HashMap::new(),
+            late_trades: 0,
+        }
+    }
+
+    /// Trades dropped because their time bar had already closed.
+    pub fn late_trades(&self) -> u64 {
+        self.late_trades
+    }
+
+    /// Feeds one update; anything but a trade is ignored.
+    pub fn on_market_data(&mut self, update: &MarketDataUpdate) -> Vec<CandleUpdate> {
+        let (Some(price), Some(quantity)) = (update.last_price, update.last_quantity) else {
+            return Vec::new();
+        };
+        let mut out = Vec::new();
+        for spec in &self.specs {
+            let series = self
+                .series
+                .entry((update.symbol.clone(), *spec))
+                .or_insert_with(|| match spec {
+                    BarSpec::Time(secs) => Series::Time {
+                        length: Duration::seconds(*secs as i64),
+                        open: BTreeMap::new(),
+                        newest_trade: None,
+                        closed_before: None,
+                    },
+                    _ => Series::Activity { current: None, progress: 0.0 },
+                });
+            let mut publish = |candle: Candlestick, closed| {
+                out.push(CandleUpdate { symbol: update.symbol.clone(), spec: *spec, candle, closed });
+            };
+
+            match series {
+                Series::Time { length, open, newest_trade, closed_before } => {
+                    let start = bucket_start(update.timestamp, *length);
+                    if closed_before.is_some_and(|closed| start < closed) {
+                        self.late_trades += 1;
+                        debug!("Dropping late {} trade at {} for closed {} bar", update.symbol, update.timestamp, spec);
+                        continue;
+                    }
+
+                    let newest = newest_trade.map_or(update.timestamp, |t| t.max(update.timestamp));
+                    *newest_trade = Some(newest);
+                    close_before(open, closed_before, bucket_start(newest - self.lateness, *length), &mut publish);
+
+                    let bar = open
+                        .entry(start)
+                        .and_modify(|bar| bar.add(update.timestamp, price, quantity))
+                        .or_insert_with(|| Bar::new(start, update.timestamp, price, quantity));
+                    publish(bar.candle.clone(), false);
+                }
+                Series::Activity { current, progress } => {
+                    // Progress per unit of quantity; tick bars count trades instead.
+                    let (threshold, per_unit) = match *spec {
+                        BarSpec::Tick(n) => (n as f64, None),
+                        BarSpec::Volume(x) => (x, Some(1.0)),
+                        BarSpec::Dollar(x) => (x, Some(price)),
+                        BarSpec::Time(_) => unreachable!(),
+                    };
+                    // A trade that crosses the threshold is split; the rest
+                    // opens the next bar, or several if it is large enough.
+                    let mut remaining = quantity;
+                    loop {
+                        let take = per_unit.map_or(remaining, |unit| remaining.min((threshold - *progress) / unit));
+                        match current {
+                            Some(bar) => bar.append(update.timestamp, price, take),
+                            None => *current = Some(Bar::new(update.timestamp, update.timestamp, price, take)),
+                        }
+                        remaining -= take;
+                        *progress += per_unit.map_or(1.0, |unit| take * unit);
+                        if *progress < threshold * (1.0 - 1e-9) {
+                            publish(current.as_ref().expect("bar opened above").candle.clone(), false);
+                            break;
+                        }
+                        *progress = 0.0;
+                        publish(current.take().expect("bar opened above").candle, true);
+                        if remaining <= QTY_EPSILON {
+                            break;
+                        }
+                    }
+                }
+            }
+        }
+        out
+    }
+
+    /// Closes time bars whose end is more than the allowed lateness before
+    /// `now`, so quiet symbols don't hold their last bar open.
+    pub fn on_timer(&mut self, now: DateTime<Utc>) -> Vec<CandleUpdate> {
+        let mut out = Vec::new();
+        for ((symbol, spec), series) in &mut self.series {
+            let Series::Time { length, open, closed_before, .. } = series else {
+                continue;
+            };
+            let mut publish = |candle, closed| {
+                out.push(CandleUpdate { symbol: symbol.clone(), spec: *spec, candle, closed });
+            };
+            close_before(open, closed_before, bucket_start(now - self.lateness, *length), &mut publish);
+        }
+        out
+    }
+}
+
+/// Publishes every open bucket starting before `watermark` as closed.
+fn close_before(
+    open: &mut BTreeMap<DateTime<Utc>, Bar>,
+    closed_before: &mut Option<DateTime<Utc>>,
+    watermark: DateTime<Utc>,
+    publish: &mut impl FnMut(Candlestick, bool),
+) {
+    *closed_before = Some(closed_before.map_or(watermark, |c| c.max(watermark)));
+    while let Some(entry) = open.first_entry() {
+        if *entry.key() >= watermark {
+            break;
+        }
+        publish(entry.remove().candle, true);
+    }
+}
+
+fn bucket_start(time: DateTime<Utc>, length: Duration) -> DateTime<Utc> {
+    let length_ms = length.num_milliseconds();
+    let start_ms = time.timestamp_millis().div_euclid(length_ms) * length_ms;
+    DateTime::from_timestamp_millis(start_ms).unwrap_or(time)
+}
+
+/// Aggregates trades from `md_rx` and publishes every bar change on
+/// `candle_tx`. With `close_on_timer`, time bars also close from the wall
+/// clock; replays leave that to the data, whose timestamps don't follow it.
+pub async fn run_candle_aggregator(
+    config: CandleConfig,
+    mut md_rx: mpsc::UnboundedReceiver<MarketDataUpdate>,
+    candle_tx: mpsc::UnboundedSender<CandleUpdate>,
+    close_on_timer: bool,
+) -> Result<()> {
+    info!("Candle aggregator started for {:?}.", config.bars.iter().map(ToString::to_string).collect::<Vec<_>>());
+    let mut aggregator = CandleAggregator::new(&config);
+    let mut timer = tokio::time::interval(tokio::time::Duration::from_millis(250));
+
+    loop {
+        let candles = tokio::select! {
+            update = md_rx.recv() => {
+                let Some(update) = update else { break };
+                aggregator.on_market_data(&update)
+            }
+            _ = timer.tick(), if close_on_timer => aggregator.on_timer(Utc::now()),
+            _ = tokio::signal::ctrl_c() => {
+                info!("Candle aggregator received Ctrl-C, shutting down.");
+                break;
+            }
+        };
+        for candle in candles {
+            if candle_tx.send(candle).is_err() {
+                info!("Candle consumers dropped, stopping aggregator.");
+                return Ok(());
+            }
+        }
+    }
+    if aggregator.late_trades() > 0 {
+        info!("Candle aggregator dropped {} late trades.", aggregator.late_trades());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+
+    fn at(secs: i64, millis: i64) -> DateTime<Utc> {
+        Utc.timestamp_opt(1_700_000_000 + secs, 0).unwrap() + Duration::milliseconds(millis)
+    }
+
+    fn trade(time: DateTime<Utc>, price: f64, quantity: f64) -> MarketDataUpdate {
+        MarketDataUpdate {
+            symbol: "BTCUSDT".to_string(),
+            timestamp: time,
+            bid_price: None,
+            bid_quantity: None,
+            ask_price: None,
+            ask_quantity: None,
+            last_price: Some(price),
+            last_quantity: Some(quantity),
+            aggressor: None,
+        }
+    }
+
+    fn aggregator(bars: Vec<BarSpec>, allowed_lateness_ms: u64) -> CandleAggregator {
+        CandleAggregator::new(&CandleConfig { bars, allowed_lateness_ms })
+    }
+
+    fn closed(updates: &[CandleUpdate]) -> Vec<Candlestick> {
+        updates.iter().filter(|u| u.closed).map(|u| u.candle.clone()).collect()
+    }
+
+    #[test]
+    fn time_bar_closes_once_the_lateness_has_passed() {
+        // 1_700_000_000 is 20s into a minute, so minute bars start at -20s.
+        let mut candles = aggregator(vec![BarSpec::Time(60)], 1_000);
+        candles.on_market_data(&trade(at(0, 0), 100.0, 1.0));
+        candles.on_market_data(&trade(at(30, 0), 103.0, 1.0));
+        candles.on_market_data(&trade(at(10, 0), 99.0, 2.0));
+
+        // Past the bar end but within the lateness: still open, and a late
+        // trade still counts.
+        assert!(closed(&candles.on_market_data(&trade(at(40, 500), 104.0, 1.0))).is_empty());
+        assert!(closed(&candles.on_market_data(&trade(at(39, 0), 98.0, 1.0))).is_empty());
+
+        let updates = candles.on_market_data(&trade(at(41, 0), 105.0, 1.0));
+        let bars = closed(&updates);
+        assert_eq!(bars.len(), 1);
+        let bar = &bars[0];
+        assert_eq!(bar.timestamp, at(-20, 0));
+        assert_eq!((bar.open, bar.high, bar.low, bar.close, bar.volume), (100.0, 103.0, 98.0, 98.0, 5.0));
+
+        candles.on_market_data(&trade(at(35, 0), 90.0, 1.0));
+        assert_eq!(candles.late_trades(), 1);
+    }
+
+    #[test]
+    fn timer_closes_bars_of_quiet_symbols() {
+        let mut candles = aggregator(vec![BarSpec::Time(60)], 1_000);
+        candles.on_market_data(&trade(at(0, 0), 100.0, 1.0));
+        assert!(candles.on_timer(at(40, 500)).is_empty());
+
+        let updates = candles.on_timer(at(41, 0));
+        assert_eq!(closed(&updates).len(), 1);
+        assert!(candles.on_timer(at(45, 0)).is_empty());
+    }
+
+    #[test]
+    fn tick_bars_close_every_n_trades() {
+        let mut candles = aggregator(vec![BarSpec::Tick(2)], 0);
+        assert!(closed(&candles.on_market_data(&trade(at(0, 0), 100.0, 1.0))).is_empty());
+        let bars = closed(&candles.on_market_data(&trade(at(1, 0), 101.0, 3.0)));
+        assert_eq!(bars.len(), 1);
+        assert_eq!((bars[0].open, bars[0].close, bars[0].volume), (100.0, 101.0, 4.0));
+    }
+
+    #[test]
+    fn volume_bar_overflow_carries_into_the_next_bars() {
+        let mut candles = aggregator(vec![BarSpec::Volume(2.0)], 0);
+        candles.on_market_data(&trade(at(0, 0), 100.0, 1.5));
+        let updates = candles.on_market_data(&trade(at(1, 0), 101.0, 3.0));
+        let bars = closed(&updates);
+        assert_eq!(bars.iter().map(|b| b.volume).collect::<Vec<_>>(), vec![2.0, 2.0]);
+        assert_eq!(bars[1].open, 101.0);
+
+        // The last 0.5 opened a third bar that is still in progress.
+        let last = updates.last().unwrap();
+        assert!(!last.closed);
+        assert_eq!(last.candle.volume, 0.5);
+    }
+
+    #[test]
+    fn dollar_bars_split_by_notional() {
+        let mut candles = aggregator(vec![BarSpec::Dollar(1_000.0)], 0);
+        let bars = closed(&candles.on_market_data(&trade(at(0, 0), 200.0, 6.0)));
+        assert_eq!(bars.len(), 1);
+        assert!((bars[0].volume - 5.0).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,153 @@
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde_json::Value;
+use url::Url;
+
+use crate::config::{CoinbaseConfig, Venue};
+use crate::data_model::{MarketDataUpdate, Side};
+use crate::market_data::source::{number_field, str_field, MarketDataSource, MarketEvent, ParseError};
+
+/// Coinbase Exchange public feed, `ticker` channel. Symbols are product ids
+/// such as `BTC-USD`. Each ticker carries the last trade and the top of book.
+pub struct CoinbaseSource {
+    config: CoinbaseConfig,
+}
+
+impl CoinbaseSource {
+    pub fn new(config: CoinbaseConfig) -> Self {
+        Self { config }
+    }
+
+    fn subscription(kind: &str, product_ids: &[String]) -> String {
+        serde_json::json!({
+            "type": kind,
+            "product_ids": product_ids,
+            "channels": ["ticker"],
+        })
+        .to_string()
+    }
+}
+
+impl MarketDataSource for CoinbaseSource {
+    fn venue(&self) -> Venue {
+        Venue::Coinbase
+    }
+
+    fn connect_url(&self, _symbols: &[String]) -> Result<Url> {
+        Ok(Url::parse(&self.config.websocket_url)?)
+    }
+
+    fn initial_frames(&self, symbols: &[String]) -> Vec<String> {
+        if symbols.is_empty() {
+            return Vec::new();
+        }
+        vec![Self::subscription("subscribe", symbols)]
+    }
+
+    fn subscription_frame(&self, symbol: &str, subscribe: bool, _request_id: u64) -> String {
+        let kind = if subscribe { "subscribe" } else { "unsubscribe" };
+        Self::subscription(kind, &[symbol.to_string()])
+    }
+
+    fn parse(&self, text: &str) -> Result<Vec<MarketEvent>, ParseError> {
+        parse_message(text)
+    }
+}
+
+pub fn parse_message(json_str: &str) -> Result<Vec<MarketEvent>, ParseError> {
+    let v: Value = serde_json::from_str(json_str)?;
+    match str_field(&v, "type")? {
+        "ticker" => Ok(vec![MarketEvent::Update(parse_ticker(&v)?)]),
+        "subscriptions" | "heartbeat" => Ok(Vec::new()),
+        "error" => {
+            let message = v.get("message").and_then(Value::as_str).unwrap_or_default();
+            let reason = v.get("reason").and_then(Value::as_str).unwrap_or_default();
+            Err(ParseError::ErrorResponse(format!("{}: {}", message, reason)))
+        }
+        other => Err(ParseError::UnsupportedEvent(other.to_string())),
+    }
+}
+
+fn parse_ticker(v: &Value) -> Result<MarketDataUpdate, ParseError> {
+    let time = str_field(v, "time")?;
+    let timestamp = DateTime::parse_from_rfc3339(time)
+        .map_err(|_| ParseError::InvalidTimestamp(time.to_string()))?
+        .with_timezone(&Utc);
+    let optional = |field| v.get(field).map(|_| number_field(v, field)).transpose();
+
+    Ok(MarketDataUpdate {
+        symbol: str_field(v, "product_id")?.to_string(),
+        timestamp,
+        bid_price: optional("best_bid")?,
+        bid_quantity: optional("best_bid_size")?,
+        ask_price: optional("best_ask")?,
+        ask_quantity: optional("best_ask_size")?,
+        last_price: Some(number_field(v, "price")?),
+        last_quantity: optional("last_size")?,
+        aggressor: match v.get("side").and_then(Value::as_str) {
+            Some("buy") => Some(Side::Buy),
+            Some("sell") => Some(Side::Sell),
+            _ => None,
+        },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TICKER: &str = include_str!("../../tests/fixtures/coinbase/ticker.json");
+    const TICKER_MISSING_PRICE: &str = include_str!("../../tests/fixtures/coinbase/ticker_missing_price.json");
+    const SUBSCRIPTIONS: &str = include_str!("../../tests/fixtures/coinbase/subscriptions.json");
+    const HEARTBEAT: &str = include_str!("../../tests/fixtures/coinbase/heartbeat.json");
+    const ERROR: &str = include_str!("../../tests/fixtures/coinbase/error.json");
+
+    #[test]
+    fn ticker_maps_to_trade_and_quote() {
+        let events = parse_message(TICKER).unwrap();
+        let [MarketEvent::Update(update)] = events.as_slice() else {
+            panic!("expected one update, got {:?}", events);
+        };
+        assert_eq!(update.symbol, "ETH-USD");
+        assert_eq!(update.timestamp.to_rfc3339(), "2022-10-19T23:28:22.061769+00:00");
+        assert_eq!(update.last_price, Some(1285.22));
+        assert_eq!(update.last_quantity, Some(11.4396987));
+        assert_eq!(update.bid_price, Some(1285.04));
+        assert_eq!(update.bid_quantity, Some(0.46688654));
+        assert_eq!(update.ask_price, Some(1285.27));
+        assert_eq!(update.ask_quantity, Some(1.5663704));
+        assert_eq!(update.aggressor, Some(Side::Buy));
+    }
+
+    #[test]
+    fn control_messages_yield_no_events() {
+        assert!(parse_message(SUBSCRIPTIONS).unwrap().is_empty());
+        assert!(parse_message(HEARTBEAT).unwrap().is_empty());
+    }
+
+    #[test]
+    fn error_message_is_surfaced() {
+        match parse_message(ERROR) {
+            Err(ParseError::ErrorResponse(msg)) => assert!(msg.contains("ETH-USDX is not a valid product")),
+            other => panic!("expected error response, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn ticker_without_price_is_rejected() {
+        assert!(matches!(parse_message(TICKER_MISSING_PRICE), Err(ParseError::MissingField("price"))));
+    }
+
+    #[test]
+    fn subscriptions_use_product_ids() {
+        let source = CoinbaseSource::new(CoinbaseConfig::default());
+        let frames = source.initial_frames(&["BTC-USD".to_string(), "ETH-USD".to_string()]);
+        let v: Value = serde_json::from_str(&frames[0]).unwrap();
+        assert_eq!(v["type"], "subscribe");
+        assert_eq!(v["product_ids"], serde_json::json!(["BTC-USD", "ETH-USD"]));
+
+        let v: Value = serde_json::from_str(&source.subscription_frame("BTC-USD", false, 7)).unwrap();
+        assert_eq!(v["type"], "unsubscribe");
+        assert_eq!(v["product_ids"], serde_json::json!(["BTC-USD"]));
+    }
+}
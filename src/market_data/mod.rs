@@ -1,13 +1,243 @@
+pub mod binance;
+pub mod candles;
+pub mod coinbase;
+pub mod order_book;
+pub mod recorder;
+pub mod replay;
+pub mod source;
+#[cfg(test)]
+mod tests;
 pub mod websocket;
-use crate::config::MarketDataConfig;
+use crate::config::{MarketDataConfig, ReconnectConfig, Venue};
 use crate::data_model::MarketDataUpdate;
+use chrono::Utc;
+use std::collections::HashMap;
+use std::fmt;
+use std::time::Duration;
 use tokio::sync::mpsc;
-use tracing::info; 
+use tracing::{error, info, warn};
 
+use source::MarketDataSource;
+use websocket::StreamExit;
+
+pub use order_book::{OrderBook, OrderBooks};
+pub use recorder::RecorderHandle;
+
+/// Runtime changes to the subscribed symbols, applied without reconnecting.
+#[derive(Debug, Clone, PartialEq)]
+pub enum MarketDataCommand {
+    Subscribe(String),
+    Unsubscribe(String),
+}
+
+impl MarketDataCommand {
+    pub fn symbol(&self) -> &str {
+        match self {
+            MarketDataCommand::Subscribe(symbol) | MarketDataCommand::Unsubscribe(symbol) => symbol,
+        }
+    }
+
+    /// Applies the command to `symbols`, returning the affected symbol if
+    /// the set changed.
+    pub fn apply(&self, symbols: &mut Vec<String>) -> Option<String> {
+        match self {
+            MarketDataCommand::Subscribe(symbol) => {
+                let symbol = symbol.trim().to_uppercase();
+                if symbol.is_empty() || symbols.contains(&symbol) {
+                    return None;
+                }
+                symbols.push(symbol.clone());
+                Some(symbol)
+            }
+            MarketDataCommand::Unsubscribe(symbol) => {
+                let symbol = symbol.trim().to_uppercase();
+                let idx = symbols.iter().position(|s| *s == symbol)?;
+                symbols.remove(idx);
+                Some(symbol)
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConnectionStatus {
+    Connecting,
+    Connected,
+    Reconnecting { attempt: u32, delay: Duration },
+    GaveUp { attempts: u32 },
+    Stopped,
+}
+
+impl fmt::Display for ConnectionStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConnectionStatus::Connecting => write!(f, "Connecting"),
+            ConnectionStatus::Connected => write!(f, "Connected"),
+            ConnectionStatus::Reconnecting { attempt, delay } => {
+                write!(f, "Reconnecting (attempt {}, in {:.1}s)", attempt, delay.as_secs_f64())
+            }
+            ConnectionStatus::GaveUp { attempts } => write!(f, "Disconnected (gave up after {} attempts)", attempts),
+            ConnectionStatus::Stopped => write!(f, "Stopped"),
+        }
+    }
+}
+
+/// Connection status of one venue's feed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct VenueStatus {
+    pub venue: Venue,
+    pub status: ConnectionStatus,
+}
+
+/// Exponential backoff capped at `max_backoff_ms`, with "equal jitter": the
+/// delay is drawn uniformly from the upper half of the current step.
+pub fn backoff_delay(policy: &ReconnectConfig, attempt: u32) -> Duration {
+    let exp = attempt.saturating_sub(1).min(31);
+    let base = policy
+        .initial_backoff_ms
+        .saturating_mul(1u64 << exp)
+        .min(policy.max_backoff_ms);
+    let half = base / 2;
+    let jitter = if half > 0 { rand::random::<u64>() % (half + 1) } else { 0 };
+    Duration::from_millis(half + jitter)
+}
+
+/// Where a feed delivers what it receives.
+#[derive(Debug, Clone)]
+pub struct FeedSinks {
+    pub books: OrderBooks,
+    pub md_tx: mpsc::UnboundedSender<MarketDataUpdate>,
+    pub recorder: RecorderHandle,
+}
+
+impl FeedSinks {
+    /// Records and forwards an update. Returns false once the consumer is
+    /// gone.
+    pub fn publish(&self, update: MarketDataUpdate) -> bool {
+        self.recorder.record_update(&update, Utc::now());
+        self.md_tx.send(update).is_ok()
+    }
+}
+
+/// Runs one feed per venue in use and routes symbol changes to the venue
+/// each symbol is configured for.
 pub async fn run_market_data_handler(
-    _config: MarketDataConfig,
-    _md_tx: mpsc::UnboundedSender<MarketDataUpdate>,
+    config: MarketDataConfig,
+    sinks: FeedSinks,
+    mut command_rx: mpsc::UnboundedReceiver<MarketDataCommand>,
+    status_tx: mpsc::UnboundedSender<VenueStatus>,
 ) -> anyhow::Result<()> {
-    info!("Market Data Handler started (dummy).");
-    Ok(())
-}
\ No newline at end of file
+    info!("Market Data Handler started for {:?}.", config.symbols);
+
+    let mut symbols_by_venue: HashMap<Venue, Vec<String>> = HashMap::new();
+    for symbol in &config.symbols {
+        symbols_by_venue.entry(config.venue_for(symbol)).or_default().push(symbol.to_uppercase());
+    }
+
+    let spawn_feed = |venue: Venue, symbols: Vec<String>| {
+        let (feed_tx, feed_rx) = mpsc::unbounded_channel();
+        let source = source::source_for(venue, &config);
+        let (policy, sinks, status_tx) = (config.reconnect.clone(), sinks.clone(), status_tx.clone());
+        tokio::spawn(async move {
+            if let Err(e) = run_venue_feed(source, symbols, feed_rx, sinks, status_tx, policy).await {
+                error!("{} market data feed exited with error: {}", venue, e);
+            }
+        });
+        feed_tx
+    };
+
+    let mut feeds: HashMap<Venue, mpsc::UnboundedSender<MarketDataCommand>> = symbols_by_venue
+        .into_iter()
+        .map(|(venue, symbols)| (venue, spawn_feed(venue, symbols)))
+        .collect();
+
+    loop {
+        tokio::select! {
+            Some(command) = command_rx.recv() => {
+                let venue = config.venue_for(command.symbol());
+                let feed = feeds.entry(venue).or_insert_with(|| spawn_feed(venue, Vec::new()));
+                if let Err(e) = feed.send(command) {
+                    error!("{} market data feed is gone: {}", venue, e);
+                    feeds.remove(&venue);
+                }
+            }
+            _ = sinks.md_tx.closed() => {
+                info!("Market data consumer dropped, stopping handler.");
+                return Ok(());
+            }
+        }
+    }
+}
+
+/// Keeps one venue's feed connected, reconnecting with backoff.
+async fn run_venue_feed(
+    source: Box<dyn MarketDataSource>,
+    mut symbols: Vec<String>,
+    mut command_rx: mpsc::UnboundedReceiver<MarketDataCommand>,
+    sinks: FeedSinks,
+    status_tx: mpsc::UnboundedSender<VenueStatus>,
+    policy: ReconnectConfig,
+) -> anyhow::Result<()> {
+    let venue = source.venue();
+    let send_status = |status| {
+        let _ = status_tx.send(VenueStatus { venue, status });
+    };
+    let mut attempt: u32 = 0;
+
+    loop {
+        send_status(ConnectionStatus::Connecting);
+
+        let result = websocket::connect_and_stream(
+            source.as_ref(),
+            &mut symbols,
+            &mut command_rx,
+            &sinks,
+            || {
+                attempt = 0;
+                send_status(ConnectionStatus::Connected);
+            },
+        )
+        .await;
+
+        match result {
+            Ok(StreamExit::ChannelClosed) => {
+                info!("Market data consumer dropped, stopping {} feed.", venue);
+                send_status(ConnectionStatus::Stopped);
+                return Ok(());
+            }
+            Ok(exit) => warn!("{} market data stream ended: {:?}", venue, exit),
+            Err(e) => error!("{} market data connection failed: {}", venue, e),
+        }
+
+        attempt += 1;
+        if let Some(max) = policy.max_retries {
+            if attempt > max {
+                error!("Giving up on {} market data after {} attempts.", venue, max);
+                send_status(ConnectionStatus::GaveUp { attempts: max });
+                return Err(anyhow::anyhow!("market data reconnect limit of {} reached", max));
+            }
+        }
+
+        let delay = backoff_delay(&policy, attempt);
+        info!("Reconnecting to {} market data (attempt {}) in {:?}", venue, attempt, delay);
+        send_status(ConnectionStatus::Reconnecting { attempt, delay });
+
+        let sleep = tokio::time::sleep(delay);
+        tokio::pin!(sleep);
+        loop {
+            tokio::select! {
+                _ = &mut sleep => break,
+                _ = sinks.md_tx.closed() => {
+                    send_status(ConnectionStatus::Stopped);
+                    return Ok(());
+                }
+                // Picked up by the next connection's stream list.
+                Some(command) = command_rx.recv() => {
+                    if let Some(symbol) = command.apply(&mut symbols) {
+                        info!("{} market data symbols now {:?} (changed {})", venue, symbols, symbol);
+                    }
+                }
+            }
+        }
+    }
+}
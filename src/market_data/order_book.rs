@@ -0,0 +1,319 @@
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+
+use chrono::{DateTime, Utc};
+use parking_lot::RwLock;
+use thiserror::Error;
+
+use crate::data_model::MarketDataUpdate;
+
+/// Diffs held while waiting for a snapshot. Older ones are dropped, which
+/// only forces another snapshot request.
+const MAX_BUFFERED_DIFFS: usize = 1_000;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PriceLevel {
+    pub price: f64,
+    pub quantity: f64,
+}
+
+/// Full book from the REST/WS-API `depth` endpoint.
+#[derive(Debug, Clone)]
+pub struct DepthSnapshot {
+    pub last_update_id: u64,
+    pub bids: Vec<PriceLevel>,
+    pub asks: Vec<PriceLevel>,
+}
+
+/// One `depthUpdate` event. Levels carry absolute quantities; zero removes
+/// the level.
+#[derive(Debug, Clone)]
+pub struct DepthDiff {
+    pub symbol: String,
+    pub event_time: DateTime<Utc>,
+    pub first_update_id: u64,
+    pub final_update_id: u64,
+    pub bids: Vec<PriceLevel>,
+    pub asks: Vec<PriceLevel>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum DiffOutcome {
+    Applied,
+    /// Held until a snapshot arrives.
+    Buffered,
+    /// Already contained in the snapshot or a previous diff.
+    Stale,
+}
+
+#[derive(Debug, Clone, PartialEq, Error)]
+pub enum BookError {
+    #[error("{symbol}: sequence gap, expected update {expected} but diff starts at {first}")]
+    Gap { symbol: String, expected: u64, first: u64 },
+    #[error("{symbol}: snapshot {snapshot_id} predates buffered diffs starting at {first}")]
+    SnapshotTooOld { symbol: String, snapshot_id: u64, first: u64 },
+}
+
+/// Price key with a total order so levels can live in a `BTreeMap`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Price(f64);
+
+impl Eq for Price {}
+
+impl PartialOrd for Price {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Price {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.total_cmp(&other.0)
+    }
+}
+
+/// L2 book for one symbol, kept in sync from a snapshot plus diff stream
+/// following Binance's rules: diffs are buffered until a snapshot arrives,
+/// diffs the snapshot already covers are dropped, and every applied diff
+/// must start right after the previous one. On a gap the book clears itself
+/// and waits for a fresh snapshot.
+#[derive(Debug, Clone)]
+pub struct OrderBook {
+    symbol: String,
+    bids: BTreeMap<Price, f64>,
+    asks: BTreeMap<Price, f64>,
+    last_update_id: Option<u64>,
+    pending: Vec<DepthDiff>,
+    updated_at: Option<DateTime<Utc>>,
+}
+
+impl OrderBook {
+    pub fn new(symbol: String) -> Self {
+        Self {
+            symbol,
+            bids: BTreeMap::new(),
+            asks: BTreeMap::new(),
+            last_update_id: None,
+            pending: Vec::new(),
+            updated_at: None,
+        }
+    }
+
+    pub fn is_synced(&self) -> bool {
+        self.last_update_id.is_some()
+    }
+
+    /// Drops all levels and buffered diffs; the book needs a new snapshot.
+    pub fn reset(&mut self) {
+        self.bids.clear();
+        self.asks.clear();
+        self.last_update_id = None;
+        self.pending.clear();
+    }
+
+    pub fn apply_diff(&mut self, diff: DepthDiff) -> Result<DiffOutcome, BookError> {
+        let Some(last) = self.last_update_id else {
+            if self.pending.len() >= MAX_BUFFERED_DIFFS {
+                self.pending.remove(0);
+            }
+            self.pending.push(diff);
+            return Ok(DiffOutcome::Buffered);
+        };
+
+        if diff.final_update_id <= last {
+            return Ok(DiffOutcome::Stale);
+        }
+        if diff.first_update_id > last + 1 {
+            let first = diff.first_update_id;
+            self.reset();
+            self.pending.push(diff);
+            return Err(BookError::Gap { symbol: self.symbol.clone(), expected: last + 1, first });
+        }
+
+        Self::apply_levels(&mut self.bids, &diff.bids);
+        Self::apply_levels(&mut self.asks, &diff.asks);
+        self.last_update_id = Some(diff.final_update_id);
+        self.updated_at = Some(diff.event_time);
+        Ok(DiffOutcome::Applied)
+    }
+
+    /// Loads a snapshot and replays the diffs buffered since. A snapshot older
+    /// than the buffer is refused and the buffer kept for the next attempt.
+    pub fn apply_snapshot(&mut self, snapshot: DepthSnapshot) -> Result<(), BookError> {
+        if let Some(first) = self.pending.first().map(|d| d.first_update_id) {
+            if snapshot.last_update_id + 1 < first {
+                return Err(BookError::SnapshotTooOld {
+                    symbol: self.symbol.clone(),
+                    snapshot_id: snapshot.last_update_id,
+                    first,
+                });
+            }
+        }
+
+        let pending = std::mem::take(&mut self.pending);
+        self.bids.clear();
+        self.asks.clear();
+        Self::apply_levels(&mut self.bids, &snapshot.bids);
+        Self::apply_levels(&mut self.asks, &snapshot.asks);
+        self.last_update_id = Some(snapshot.last_update_id);
+
+        for diff in pending {
+            self.apply_diff(diff)?;
+        }
+        Ok(())
+    }
+
+    fn apply_levels(side: &mut BTreeMap<Price, f64>, levels: &[PriceLevel]) {
+        for level in levels {
+            if level.quantity > 0.0 {
+                side.insert(Price(level.price), level.quantity);
+            } else {
+                side.remove(&Price(level.price));
+            }
+        }
+    }
+
+    pub fn best_bid(&self) -> Option<PriceLevel> {
+        self.bids.iter().next_back().map(|(p, q)| PriceLevel { price: p.0, quantity: *q })
+    }
+
+    pub fn best_ask(&self) -> Option<PriceLevel> {
+        self.asks.iter().next().map(|(p, q)| PriceLevel { price: p.0, quantity: *q })
+    }
+
+    pub fn mid_price(&self) -> Option<f64> {
+        Some((self.best_bid()?.price + self.best_ask()?.price) / 2.0)
+    }
+
+    /// Best `n` bids, highest first.
+    pub fn top_bids(&self, n: usize) -> Vec<PriceLevel> {
+        self.bids.iter().rev().take(n).map(|(p, q)| PriceLevel { price: p.0, quantity: *q }).collect()
+    }
+
+    /// Best `n` asks, lowest first.
+    pub fn top_asks(&self, n: usize) -> Vec<PriceLevel> {
+        self.asks.iter().take(n).map(|(p, q)| PriceLevel { price: p.0, quantity: *q }).collect()
+    }
+
+    /// Top of book as a quote update for the rest of the pipeline.
+    pub fn quote(&self) -> Option<MarketDataUpdate> {
+        let (bid, ask) = (self.best_bid(), self.best_ask());
+        if bid.is_none() && ask.is_none() {
+            return None;
+        }
+        Some(MarketDataUpdate {
+            symbol: self.symbol.clone(),
+            timestamp: self.updated_at.unwrap_or_else(Utc::now),
+            bid_price: bid.map(|l| l.price),
+            bid_quantity: bid.map(|l| l.quantity),
+            ask_price: ask.map(|l| l.price),
+            ask_quantity: ask.map(|l| l.quantity),
+            last_price: None,
+            last_quantity: None,
+            aggressor: None,
+        })
+    }
+}
+
+/// Books shared between the market data task, which writes them, and the
+/// strategy engine and UI, which read them.
+#[derive(Debug, Clone, Default)]
+pub struct OrderBooks {
+    books: Arc<RwLock<HashMap<String, OrderBook>>>,
+}
+
+impl OrderBooks {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Runs `f` against the book for `symbol`, if one exists.
+    pub fn read<R>(&self, symbol: &str, f: impl FnOnce(&OrderBook) -> R) -> Option<R> {
+        self.books.read().get(symbol).map(f)
+    }
+
+    pub fn remove(&self, symbol: &str) {
+        self.books.write().remove(symbol);
+    }
+
+    /// Runs `f` against the book for `symbol`, creating an empty one first.
+    pub fn write<R>(&self, symbol: &str, f: impl FnOnce(&mut OrderBook) -> R) -> R {
+        let mut books = self.books.write();
+        let book = books
+            .entry(symbol.to_string())
+            .or_insert_with(|| OrderBook::new(symbol.to_string()));
+        f(book)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn level(price: f64, quantity: f64) -> PriceLevel {
+        PriceLevel { price, quantity }
+    }
+
+    fn diff(first: u64, last: u64, bids: Vec<PriceLevel>, asks: Vec<PriceLevel>) -> DepthDiff {
+        DepthDiff {
+            symbol: "BTCUSDT".to_string(),
+            event_time: Utc::now(),
+            first_update_id: first,
+            final_update_id: last,
+            bids,
+            asks,
+        }
+    }
+
+    fn snapshot(last_update_id: u64) -> DepthSnapshot {
+        DepthSnapshot {
+            last_update_id,
+            bids: vec![level(99.0, 1.0), level(98.0, 2.0)],
+            asks: vec![level(101.0, 1.0), level(102.0, 2.0)],
+        }
+    }
+
+    #[test]
+    fn buffers_until_snapshot_then_replays_newer_diffs() {
+        let mut book = OrderBook::new("BTCUSDT".to_string());
+        assert_eq!(book.apply_diff(diff(95, 100, vec![level(97.0, 5.0)], vec![])), Ok(DiffOutcome::Buffered));
+        assert_eq!(book.apply_diff(diff(101, 105, vec![level(99.0, 0.0)], vec![level(100.5, 3.0)])), Ok(DiffOutcome::Buffered));
+        assert!(!book.is_synced());
+
+        book.apply_snapshot(snapshot(100)).unwrap();
+        assert!(book.is_synced());
+        // The first diff is covered by the snapshot, the second applies on top.
+        assert_eq!(book.top_bids(5), vec![level(98.0, 2.0)]);
+        assert_eq!(book.best_ask(), Some(level(100.5, 3.0)));
+        assert_eq!(book.apply_diff(diff(103, 105, vec![], vec![])), Ok(DiffOutcome::Stale));
+        assert_eq!(book.apply_diff(diff(106, 106, vec![level(98.5, 1.0)], vec![])), Ok(DiffOutcome::Applied));
+        assert_eq!(book.best_bid(), Some(level(98.5, 1.0)));
+    }
+
+    #[test]
+    fn gap_clears_the_book_and_keeps_the_diff_for_the_next_snapshot() {
+        let mut book = OrderBook::new("BTCUSDT".to_string());
+        book.apply_snapshot(snapshot(100)).unwrap();
+        let err = book.apply_diff(diff(103, 110, vec![level(99.5, 1.0)], vec![])).unwrap_err();
+        assert_eq!(err, BookError::Gap { symbol: "BTCUSDT".to_string(), expected: 101, first: 103 });
+        assert!(!book.is_synced());
+        assert_eq!(book.best_bid(), None);
+
+        book.apply_snapshot(snapshot(105)).unwrap();
+        assert_eq!(book.best_bid(), Some(level(99.5, 1.0)));
+    }
+
+    #[test]
+    fn snapshot_older_than_the_buffer_is_refused() {
+        let mut book = OrderBook::new("BTCUSDT".to_string());
+        book.apply_diff(diff(200, 205, vec![], vec![])).unwrap();
+        let err = book.apply_snapshot(snapshot(150)).unwrap_err();
+        assert_eq!(err, BookError::SnapshotTooOld { symbol: "BTCUSDT".to_string(), snapshot_id: 150, first: 200 });
+        assert!(!book.is_synced());
+
+        book.apply_snapshot(snapshot(199)).unwrap();
+        assert!(book.is_synced());
+        assert_eq!(book.apply_diff(diff(206, 206, vec![], vec![])), Ok(DiffOutcome::Applied));
+    }
+}
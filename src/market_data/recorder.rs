@@ -0,0 +1,278 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::PathBuf;
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde::{Deserialize, Serialize};
+use tokio::sync::mpsc;
+use tracing::{error, info};
+
+use crate::config::{RecorderConfig, Venue};
+use crate::data_model::MarketDataUpdate;
+
+/// File name prefix of normalized update recordings.
+pub const UPDATES_PREFIX: &str = "updates";
+
+/// One line of a raw frame recording.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordedFrame {
+    pub received_at: DateTime<Utc>,
+    pub venue: Venue,
+    pub frame: String,
+}
+
+/// One line of an update recording.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordedUpdate {
+    pub received_at: DateTime<Utc>,
+    pub update: MarketDataUpdate,
+}
+
+#[derive(Debug)]
+pub enum RecordEvent {
+    Frame { symbol: Option<String>, frame: RecordedFrame },
+    Update(RecordedUpdate),
+}
+
+/// Cheap handle the feeds use to tee data to the recorder task. A disabled
+/// handle drops everything.
+#[derive(Debug, Clone, Default)]
+pub struct RecorderHandle {
+    tx: Option<mpsc::UnboundedSender<RecordEvent>>,
+}
+
+impl RecorderHandle {
+    /// A handle plus the receiving end for `run_recorder`.
+    pub fn channel() -> (Self, mpsc::UnboundedReceiver<RecordEvent>) {
+        let (tx, rx) = mpsc::unbounded_channel();
+        (Self { tx: Some(tx) }, rx)
+    }
+
+    pub fn disabled() -> Self {
+        Self::default()
+    }
+
+    /// `symbol` is the instrument the frame was about, if it could be parsed.
+    pub fn record_frame(&self, venue: Venue, symbol: Option<&str>, frame: &str, received_at: DateTime<Utc>) {
+        if let Some(tx) = &self.tx {
+            let _ = tx.send(RecordEvent::Frame {
+                symbol: symbol.map(str::to_string),
+                frame: RecordedFrame { received_at, venue, frame: frame.to_string() },
+            });
+        }
+    }
+
+    pub fn record_update(&self, update: &MarketDataUpdate, received_at: DateTime<Utc>) {
+        if let Some(tx) = &self.tx {
+            let _ = tx.send(RecordEvent::Update(RecordedUpdate { received_at, update: update.clone() }));
+        }
+    }
+}
+
+/// Gzip'd JSONL file that rolls over to a new file by age or size.
+struct RotatingWriter {
+    directory: PathBuf,
+    prefix: String,
+    max_age: chrono::Duration,
+    max_bytes: u64,
+    current: Option<(GzEncoder<BufWriter<File>>, DateTime<Utc>, u64)>,
+}
+
+impl RotatingWriter {
+    fn new(config: &RecorderConfig, prefix: String) -> Self {
+        Self {
+            directory: PathBuf::from(&config.directory),
+            prefix,
+            max_age: chrono::Duration::minutes(config.rotate_minutes.max(1) as i64),
+            max_bytes: config.rotate_megabytes.max(1) * 1024 * 1024,
+            current: None,
+        }
+    }
+
+    fn write_line(&mut self, line: &str, now: DateTime<Utc>) -> Result<()> {
+        let expired = self
+            .current
+            .as_ref()
+            .is_some_and(|(_, opened, written)| now - *opened >= self.max_age || *written >= self.max_bytes);
+        if expired {
+            self.finish()?;
+        }
+        if self.current.is_none() {
+            let path = self
+                .directory
+                .join(format!("{}-{}.jsonl.gz", self.prefix, now.format("%Y%m%dT%H%M%S%.3fZ")));
+            let file = File::create(&path).with_context(|| format!("Failed to create {}", path.display()))?;
+            info!("Recording to {}", path.display());
+            self.current = Some((GzEncoder::new(BufWriter::new(file), Compression::default()), now, 0));
+        }
+
+        let (encoder, _, written) = self.current.as_mut().expect("writer opened above");
+        encoder.write_all(line.as_bytes())?;
+        encoder.write_all(b"\n")?;
+        *written += line.len() as u64 + 1;
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        if let Some((encoder, _, _)) = self.current.as_mut() {
+            encoder.flush()?;
+        }
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<()> {
+        if let Some((encoder, _, _)) = self.current.take() {
+            encoder.finish()?.flush()?;
+        }
+        Ok(())
+    }
+}
+
+struct Recorder {
+    config: RecorderConfig,
+    raw: HashMap<Venue, RotatingWriter>,
+    updates: RotatingWriter,
+}
+
+impl Recorder {
+    fn wants(&self, symbol: Option<&str>) -> bool {
+        match symbol {
+            _ if self.config.symbols.is_empty() => true,
+            Some(symbol) => self.config.symbols.iter().any(|s| s.eq_ignore_ascii_case(symbol)),
+            None => false,
+        }
+    }
+
+    fn record(&mut self, event: RecordEvent) -> Result<()> {
+        match event {
+            RecordEvent::Frame { symbol, frame } => {
+                if !self.config.raw_frames || !self.wants(symbol.as_deref()) {
+                    return Ok(());
+                }
+                let config = &self.config;
+                let writer = self
+                    .raw
+                    .entry(frame.venue)
+                    .or_insert_with(|| RotatingWriter::new(config, format!("raw-{}", frame.venue).to_lowercase()));
+                writer.write_line(&serde_json::to_string(&frame)?, frame.received_at)
+            }
+            RecordEvent::Update(recorded) => {
+                if !self.config.updates || !self.wants(Some(&recorded.update.symbol)) {
+                    return Ok(());
+                }
+                self.updates.write_line(&serde_json::to_string(&recorded)?, recorded.received_at)
+            }
+        }
+    }
+
+    fn writers(&mut self) -> impl Iterator<Item = &mut RotatingWriter> {
+        self.raw.values_mut().chain(std::iter::once(&mut self.updates))
+    }
+}
+
+pub async fn run_recorder(config: RecorderConfig, mut rx: mpsc::UnboundedReceiver<RecordEvent>) -> Result<()> {
+    std::fs::create_dir_all(&config.directory)
+        .with_context(|| format!("Failed to create recording directory {}", config.directory))?;
+    info!("Market data recorder writing to {} (symbols: {:?}).", config.directory, config.symbols);
+    let updates = RotatingWriter::new(&config, UPDATES_PREFIX.to_string());
+    let mut recorder = Recorder { config, raw: HashMap::new(), updates };
+    // Bounds what a crash can lose; gzip buffers internally otherwise.
+    let mut flush = tokio::time::interval(tokio::time::Duration::from_secs(5));
+
+    loop {
+        tokio::select! {
+            event = rx.recv() => {
+                let Some(event) = event else { break };
+                if let Err(e) = recorder.record(event) {
+                    error!("Failed to record market data: {:#}", e);
+                }
+            }
+            _ = flush.tick() => {
+                for writer in recorder.writers() {
+                    if let Err(e) = writer.flush() {
+                        error!("Failed to flush market data recording: {}", e);
+                    }
+                }
+            }
+            _ = tokio::signal::ctrl_c() => {
+                info!("Recorder received Ctrl-C, closing files.");
+                break;
+            }
+        }
+    }
+
+    for writer in recorder.writers() {
+        writer.finish()?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::*;
+    use crate::backtest::loader::load_market_data;
+
+    fn trade(symbol: &str, at: DateTime<Utc>, price: f64) -> MarketDataUpdate {
+        MarketDataUpdate {
+            symbol: symbol.to_string(),
+            timestamp: at,
+            bid_price: None,
+            bid_quantity: None,
+            ask_price: None,
+            ask_quantity: None,
+            last_price: Some(price),
+            last_quantity: Some(1.0),
+            aggressor: None,
+        }
+    }
+
+    fn files_with_prefix(dir: &Path, prefix: &str) -> usize {
+        std::fs::read_dir(dir)
+            .unwrap()
+            .filter(|e| e.as_ref().unwrap().file_name().to_string_lossy().starts_with(prefix))
+            .count()
+    }
+
+    #[tokio::test]
+    async fn recorded_updates_rotate_and_load_back() {
+        let dir = std::env::temp_dir().join(format!("recorder_test_{}", uuid::Uuid::new_v4()));
+        let config = RecorderConfig {
+            enabled: true,
+            directory: dir.to_string_lossy().into_owned(),
+            symbols: vec!["btcusdt".to_string()],
+            rotate_minutes: 60,
+            ..Default::default()
+        };
+        let (handle, rx) = RecorderHandle::channel();
+        let recorder = tokio::spawn(run_recorder(config, rx));
+
+        let start = DateTime::from_timestamp_millis(1718000000000).unwrap();
+        let times = [start, start + chrono::Duration::minutes(30), start + chrono::Duration::minutes(61)];
+        for (i, at) in times.iter().enumerate() {
+            handle.record_update(&trade("BTCUSDT", *at, 100.0 + i as f64), *at);
+            handle.record_update(&trade("ETHUSDT", *at, 5.0), *at);
+        }
+        handle.record_frame(Venue::Binance, Some("BTCUSDT"), "{\"e\":\"trade\"}", start);
+        handle.record_frame(Venue::Binance, None, "{\"result\":null}", start);
+        drop(handle);
+        recorder.await.unwrap().unwrap();
+
+        let update_files = files_with_prefix(&dir, UPDATES_PREFIX);
+        let raw_files = files_with_prefix(&dir, "raw-binance");
+        let loaded = load_market_data(&dir);
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(update_files, 2, "the third update is past rotate_minutes");
+        assert_eq!(raw_files, 1);
+        let loaded = loaded.unwrap();
+        assert!(loaded.iter().all(|u| u.symbol == "BTCUSDT"));
+        assert_eq!(loaded.iter().map(|u| u.timestamp).collect::<Vec<_>>(), times);
+        assert_eq!(loaded.iter().map(|u| u.last_price).collect::<Vec<_>>(), vec![Some(100.0), Some(101.0), Some(102.0)]);
+    }
+}
@@ -0,0 +1,294 @@
+use std::fmt;
+
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Utc};
+use tokio::sync::mpsc;
+use tokio::time::{Duration, Instant};
+use tracing::{info, warn};
+
+use crate::config::ReplayConfig;
+use crate::data_model::MarketDataUpdate;
+use crate::market_data::MarketDataCommand;
+
+/// Minimum interval between progress reports while playing.
+const STATUS_INTERVAL: Duration = Duration::from_millis(100);
+/// Updates sent back to back before yielding at full speed.
+const MAX_SPEED_BATCH: usize = 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ReplaySpeed {
+    /// No pacing; updates are sent back to back.
+    AsFastAsPossible,
+    /// Multiple of the recorded pace; 1.0 is real time.
+    Multiplier(f64),
+}
+
+impl ReplaySpeed {
+    pub fn from_multiplier(multiplier: f64) -> Self {
+        if multiplier.is_finite() && multiplier > 0.0 {
+            ReplaySpeed::Multiplier(multiplier)
+        } else {
+            ReplaySpeed::AsFastAsPossible
+        }
+    }
+}
+
+impl fmt::Display for ReplaySpeed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReplaySpeed::AsFastAsPossible => write!(f, "Max"),
+            ReplaySpeed::Multiplier(m) => write!(f, "{}x", m),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ReplayCommand {
+    Play,
+    Pause,
+    SetSpeed(ReplaySpeed),
+    /// Skip ahead to the first update at or after the given time. Seeking
+    /// back is refused: bars, books, strategies and the OMS have already
+    /// consumed the later data and would reject or double count it.
+    Seek(DateTime<Utc>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReplayStatus {
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+    /// Timestamp of the next update to be sent, or `end` once finished.
+    pub position: DateTime<Utc>,
+    pub sent: usize,
+    pub total: usize,
+    pub paused: bool,
+    pub speed: ReplaySpeed,
+}
+
+impl ReplayStatus {
+    pub fn finished(&self) -> bool {
+        self.sent >= self.total
+    }
+}
+
+/// UI side of a running replay.
+pub struct ReplayHandle {
+    pub command_tx: mpsc::UnboundedSender<ReplayCommand>,
+    pub status_rx: mpsc::UnboundedReceiver<ReplayStatus>,
+}
+
+struct Replay {
+    updates: Vec<MarketDataUpdate>,
+    next: usize,
+    paused: bool,
+    speed: ReplaySpeed,
+    /// Wall-clock instant and recorded time the current pacing run started
+    /// from; cleared whenever playback is interrupted.
+    anchor: Option<(Instant, DateTime<Utc>)>,
+    symbols: Vec<String>,
+}
+
+impl Replay {
+    fn status(&self) -> ReplayStatus {
+        let start = self.updates[0].timestamp;
+        let end = self.updates[self.updates.len() - 1].timestamp;
+        ReplayStatus {
+            start,
+            end,
+            position: self.updates.get(self.next).map_or(end, |u| u.timestamp),
+            sent: self.next,
+            total: self.updates.len(),
+            paused: self.paused,
+            speed: self.speed,
+        }
+    }
+
+    fn apply(&mut self, command: ReplayCommand) {
+        info!("Replay: {:?}", command);
+        match command {
+            ReplayCommand::Play => self.paused = false,
+            ReplayCommand::Pause => self.paused = true,
+            ReplayCommand::SetSpeed(speed) => self.speed = speed,
+            ReplayCommand::Seek(time) => {
+                let target = self.updates.partition_point(|u| u.timestamp < time);
+                if target < self.next {
+                    warn!("Replay: refusing to seek back to {}; restart the replay to watch it again", time);
+                } else {
+                    self.next = target;
+                }
+            }
+        }
+        self.anchor = None;
+    }
+
+    /// When the next update is due, or `None` if it can go out now.
+    fn due(&mut self, update: &MarketDataUpdate) -> Option<Instant> {
+        let ReplaySpeed::Multiplier(multiplier) = self.speed else {
+            return None;
+        };
+        let (started, from) = *self.anchor.get_or_insert((Instant::now(), update.timestamp));
+        let offset = (update.timestamp - from).to_std().unwrap_or_default();
+        Some(started + offset.div_f64(multiplier))
+    }
+}
+
+/// Plays `updates` (sorted by timestamp) into `md_tx` until the consumer
+/// goes away. Symbol commands filter which recorded symbols are emitted.
+pub async fn run_replay(
+    config: ReplayConfig,
+    updates: Vec<MarketDataUpdate>,
+    md_tx: mpsc::UnboundedSender<MarketDataUpdate>,
+    mut md_command_rx: mpsc::UnboundedReceiver<MarketDataCommand>,
+    mut command_rx: mpsc::UnboundedReceiver<ReplayCommand>,
+    status_tx: mpsc::UnboundedSender<ReplayStatus>,
+) -> Result<()> {
+    if updates.is_empty() {
+        return Err(anyhow!("Nothing to replay in {}", config.path));
+    }
+    let mut symbols: Vec<String> = Vec::new();
+    for update in &updates {
+        MarketDataCommand::Subscribe(update.symbol.clone()).apply(&mut symbols);
+    }
+    let mut replay = Replay {
+        updates,
+        next: 0,
+        paused: config.start_paused,
+        speed: ReplaySpeed::from_multiplier(config.speed),
+        anchor: None,
+        symbols,
+    };
+    info!(
+        "Replaying {} updates for {:?} from {} at {}.",
+        replay.updates.len(),
+        replay.symbols,
+        config.path,
+        replay.speed
+    );
+
+    let mut last_status = Instant::now();
+    let _ = status_tx.send(replay.status());
+    let mut batch = 0;
+
+    loop {
+        let update = match replay.updates.get(replay.next) {
+            Some(update) if !replay.paused => update.clone(),
+            _ => {
+                tokio::select! {
+                    Some(command) = command_rx.recv() => replay.apply(command),
+                    Some(command) = md_command_rx.recv() => {
+                        command.apply(&mut replay.symbols);
+                    }
+                    _ = md_tx.closed() => break,
+                    _ = tokio::signal::ctrl_c() => {
+                        info!("Replay received Ctrl-C, stopping.");
+                        break;
+                    }
+                }
+                let _ = status_tx.send(replay.status());
+                continue;
+            }
+        };
+
+        if let Some(due) = replay.due(&update) {
+            tokio::select! {
+                _ = tokio::time::sleep_until(due) => {}
+                Some(command) = command_rx.recv() => {
+                    replay.apply(command);
+                    let _ = status_tx.send(replay.status());
+                    continue;
+                }
+                Some(command) = md_command_rx.recv() => {
+                    command.apply(&mut replay.symbols);
+                    continue;
+                }
+                _ = md_tx.closed() => break,
+                _ = tokio::signal::ctrl_c() => {
+                    info!("Replay received Ctrl-C, stopping.");
+                    break;
+                }
+            }
+        } else {
+            batch += 1;
+            if batch >= MAX_SPEED_BATCH {
+                batch = 0;
+                tokio::task::yield_now().await;
+                while let Ok(command) = command_rx.try_recv() {
+                    replay.apply(command);
+                }
+                while let Ok(command) = md_command_rx.try_recv() {
+                    command.apply(&mut replay.symbols);
+                }
+                continue;
+            }
+        }
+
+        replay.next += 1;
+        if replay.symbols.contains(&update.symbol.to_uppercase()) && md_tx.send(update).is_err() {
+            break;
+        }
+        if replay.next == replay.updates.len() {
+            info!("Replay finished after {} updates.", replay.next);
+            let _ = status_tx.send(replay.status());
+        } else if last_status.elapsed() >= STATUS_INTERVAL {
+            last_status = Instant::now();
+            let _ = status_tx.send(replay.status());
+        }
+    }
+
+    info!("Replay stopped at update {} of {}.", replay.next, replay.updates.len());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+
+    fn replay(seconds: &[i64]) -> Replay {
+        let updates = seconds
+            .iter()
+            .map(|s| MarketDataUpdate {
+                symbol: "BTCUSDT".to_string(),
+                timestamp: Utc.timestamp_opt(1_700_000_000 + s, 0).unwrap(),
+                bid_price: None,
+                bid_quantity: None,
+                ask_price: None,
+                ask_quantity: None,
+                last_price: Some(100.0),
+                last_quantity: Some(1.0),
+                aggressor: None,
+            })
+            .collect();
+        Replay {
+            updates,
+            next: 0,
+            paused: false,
+            speed: ReplaySpeed::AsFastAsPossible,
+            anchor: None,
+            symbols: vec!["BTCUSDT".to_string()],
+        }
+    }
+
+    fn at(secs: i64) -> DateTime<Utc> {
+        Utc.timestamp_opt(1_700_000_000 + secs, 0).unwrap()
+    }
+
+    #[test]
+    fn seeks_forward_to_the_first_update_at_or_after_the_time() {
+        let mut replay = replay(&[0, 10, 20, 30]);
+        replay.apply(ReplayCommand::Seek(at(15)));
+        assert_eq!(replay.next, 2);
+        replay.apply(ReplayCommand::Seek(at(20)));
+        assert_eq!(replay.next, 2);
+        assert_eq!(replay.status().position, at(20));
+    }
+
+    #[test]
+    fn refuses_to_seek_back() {
+        let mut replay = replay(&[0, 10, 20, 30]);
+        replay.next = 3;
+        replay.apply(ReplayCommand::Seek(at(5)));
+        assert_eq!(replay.next, 3);
+    }
+}
@@ -0,0 +1,97 @@
+use anyhow::Result;
+use futures_util::future::BoxFuture;
+use serde_json::Value;
+use thiserror::Error;
+use url::Url;
+
+use crate::config::{MarketDataConfig, Venue};
+use crate::data_model::{Candlestick, MarketDataUpdate};
+use crate::market_data::binance::BinanceSource;
+use crate::market_data::coinbase::CoinbaseSource;
+use crate::market_data::order_book::{DepthDiff, DepthSnapshot};
+
+#[derive(Debug, Error)]
+pub enum ParseError {
+    #[error("invalid JSON: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("unsupported event type '{0}'")]
+    UnsupportedEvent(String),
+    #[error("missing field '{0}'")]
+    MissingField(&'static str),
+    #[error("invalid number in field '{field}': {value}")]
+    InvalidNumber { field: &'static str, value: String },
+    #[error("invalid timestamp {0}")]
+    InvalidTimestamp(String),
+    #[error("request failed: {0}")]
+    ErrorResponse(String),
+}
+
+/// A venue message in normalized form.
+#[derive(Debug, Clone)]
+pub enum MarketEvent {
+    /// Trades and top-of-book quotes.
+    Update(MarketDataUpdate),
+    /// Incremental L2 book change.
+    Depth(DepthDiff),
+    Candle {
+        symbol: String,
+        interval: String,
+        /// The candle will not change any more.
+        closed: bool,
+        candle: Candlestick,
+    },
+}
+
+impl MarketEvent {
+    pub fn symbol(&self) -> &str {
+        match self {
+            MarketEvent::Update(update) => &update.symbol,
+            MarketEvent::Depth(diff) => &diff.symbol,
+            MarketEvent::Candle { symbol, .. } => symbol,
+        }
+    }
+}
+
+/// A venue's WebSocket feed: how to connect and subscribe, and how to turn
+/// its frames into `MarketEvent`s. The connection itself is driven by
+/// `websocket::connect_and_stream`.
+pub trait MarketDataSource: Send + Sync {
+    fn venue(&self) -> Venue;
+
+    fn connect_url(&self, symbols: &[String]) -> Result<Url>;
+
+    /// Frames to send right after connecting.
+    fn initial_frames(&self, _symbols: &[String]) -> Vec<String> {
+        Vec::new()
+    }
+
+    /// Frame adding (`subscribe = true`) or removing a symbol on a live
+    /// connection.
+    fn subscription_frame(&self, symbol: &str, subscribe: bool, request_id: u64) -> String;
+
+    /// Decodes one text frame. Control messages such as subscription acks
+    /// yield no events.
+    fn parse(&self, text: &str) -> Result<Vec<MarketEvent>, ParseError>;
+
+    /// Fetches a full book for venues whose depth stream carries diffs.
+    fn depth_snapshot(&self, _symbol: &str) -> Option<BoxFuture<'static, Result<DepthSnapshot>>> {
+        None
+    }
+}
+
+pub fn source_for(venue: Venue, config: &MarketDataConfig) -> Box<dyn MarketDataSource> {
+    match venue {
+        Venue::Binance => Box::new(BinanceSource::new(config.clone())),
+        Venue::Coinbase => Box::new(CoinbaseSource::new(config.coinbase.clone())),
+    }
+}
+
+pub fn str_field<'a>(v: &'a Value, field: &'static str) -> Result<&'a str, ParseError> {
+    v.get(field).and_then(Value::as_str).ok_or(ParseError::MissingField(field))
+}
+
+/// Venues send prices and sizes as decimal strings.
+pub fn number_field(v: &Value, field: &'static str) -> Result<f64, ParseError> {
+    let raw = str_field(v, field)?;
+    raw.parse().map_err(|_| ParseError::InvalidNumber { field, value: raw.to_string() })
+}
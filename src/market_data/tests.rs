@@ -0,0 +1,233 @@
+use std::time::Duration;
+
+use tokio::sync::mpsc;
+
+use super::*;
+use crate::config::{AppConfig, StreamKind};
+use crate::data_model::Side;
+use crate::market_data::binance::BinanceSource;
+use crate::market_data::websocket::connect_and_stream;
+use crate::test_support::{ClientEvent, MockExchange, Step};
+
+const TRADE: &str = include_str!("../../tests/fixtures/binance/trade.json");
+const BOOK_TICKER: &str = include_str!("../../tests/fixtures/binance/book_ticker.json");
+const DEPTH_UPDATE: &str = include_str!("../../tests/fixtures/binance/depth_update.json");
+const DEPTH_SNAPSHOT: &str = include_str!("../../tests/fixtures/binance/depth_snapshot.json");
+const MALFORMED: &str = include_str!("../../tests/fixtures/binance/malformed.json");
+
+fn text(frame: &str) -> Step {
+    Step::Text(frame.trim().to_string())
+}
+
+fn binance_config(url: &str, snapshot_url: Option<&str>) -> MarketDataConfig {
+    let mut config = AppConfig::default().market_data;
+    config.websocket_url = url.to_string();
+    config.symbols = vec!["BTCUSDT".to_string()];
+    config.streams = vec![StreamKind::Trade, StreamKind::BookTicker];
+    config.order_book.enabled = snapshot_url.is_some();
+    config.order_book.snapshot_url = snapshot_url.unwrap_or_default().to_string();
+    config.reconnect = ReconnectConfig { initial_backoff_ms: 10, max_backoff_ms: 20, max_retries: None };
+    config
+}
+
+fn sinks() -> (FeedSinks, mpsc::UnboundedReceiver<MarketDataUpdate>) {
+    let (md_tx, md_rx) = mpsc::unbounded_channel();
+    (FeedSinks { books: OrderBooks::new(), md_tx, recorder: RecorderHandle::disabled() }, md_rx)
+}
+
+async fn recv<T>(rx: &mut mpsc::UnboundedReceiver<T>) -> T {
+    tokio::time::timeout(Duration::from_secs(5), rx.recv())
+        .await
+        .expect("timed out waiting for message")
+        .expect("channel closed")
+}
+
+#[tokio::test]
+async fn streams_trades_and_quotes_skipping_malformed_frames() {
+    let mut exchange = MockExchange::start(vec![vec![text(TRADE), text(MALFORMED), text(BOOK_TICKER), Step::Close]]).await;
+    let source = BinanceSource::new(binance_config(&exchange.url(), None));
+    let (sinks, mut md_rx) = sinks();
+    let (_command_tx, mut command_rx) = mpsc::unbounded_channel();
+    let mut symbols = vec!["BTCUSDT".to_string()];
+
+    let exit = connect_and_stream(&source, &mut symbols, &mut command_rx, &sinks, || {}).await.unwrap();
+    assert_eq!(exit, StreamExit::ServerClosed);
+
+    let ClientEvent::Connected { path, .. } = exchange.next_event().await else {
+        panic!("expected a connection");
+    };
+    assert_eq!(path, "/stream?streams=btcusdt@trade/btcusdt@bookTicker");
+
+    let trade = md_rx.try_recv().unwrap();
+    assert_eq!(trade.symbol, "BTCUSDT");
+    assert_eq!(trade.last_price, Some(67000.5));
+    assert_eq!(trade.last_quantity, Some(0.25));
+    assert_eq!(trade.timestamp.timestamp_millis(), 1718000000099);
+    assert_eq!(trade.aggressor, Some(Side::Sell));
+
+    let quote = md_rx.try_recv().unwrap();
+    assert_eq!((quote.bid_price, quote.bid_quantity), (Some(67000.1), Some(1.5)));
+    assert_eq!((quote.ask_price, quote.ask_quantity), (Some(67000.2), Some(2.0)));
+    assert!(md_rx.try_recv().is_err());
+    // No depth feed configured, so no book to show as synchronizing.
+    assert!(sinks.books.read("BTCUSDT", |_| ()).is_none());
+}
+
+#[tokio::test]
+async fn depth_diffs_sync_the_book_from_a_snapshot() {
+    let exchange = MockExchange::start(vec![vec![text(DEPTH_UPDATE)]]).await;
+    let mut api = MockExchange::start(vec![vec![Step::AwaitText, text(DEPTH_SNAPSHOT)]]).await;
+    let source = BinanceSource::new(binance_config(&exchange.url(), Some(&api.url())));
+    let (sinks, mut md_rx) = sinks();
+    let (_command_tx, mut command_rx) = mpsc::unbounded_channel();
+    let mut symbols = vec!["BTCUSDT".to_string()];
+
+    let quote = tokio::select! {
+        quote = recv(&mut md_rx) => quote,
+        exit = connect_and_stream(&source, &mut symbols, &mut command_rx, &sinks, || {}) => panic!("stream ended: {:?}", exit),
+    };
+    // The diff moved the best bid and emptied the best ask level.
+    assert_eq!((quote.bid_price, quote.bid_quantity), (Some(67000.0), Some(3.0)));
+    assert_eq!((quote.ask_price, quote.ask_quantity), (Some(67002.0), Some(1.0)));
+    assert_eq!(sinks.books.read("BTCUSDT", |book| book.is_synced()), Some(true));
+
+    let ClientEvent::Text { text, .. } = api.wait_for(|e| matches!(e, ClientEvent::Text { .. })).await else {
+        unreachable!();
+    };
+    let request: serde_json::Value = serde_json::from_str(&text).unwrap();
+    assert_eq!(request["method"], "depth");
+    assert_eq!(request["params"]["symbol"], "BTCUSDT");
+}
+
+#[tokio::test]
+async fn answers_pings_with_matching_pongs() {
+    let mut exchange = MockExchange::start(vec![vec![Step::Ping(b"hb-1".to_vec())]]).await;
+    let source = BinanceSource::new(binance_config(&exchange.url(), None));
+    let (sinks, _md_rx) = sinks();
+    let (_command_tx, mut command_rx) = mpsc::unbounded_channel();
+    let mut symbols = vec!["BTCUSDT".to_string()];
+
+    let pong = tokio::select! {
+        pong = exchange.wait_for(|e| matches!(e, ClientEvent::Pong { .. })) => pong,
+        exit = connect_and_stream(&source, &mut symbols, &mut command_rx, &sinks, || {}) => panic!("stream ended: {:?}", exit),
+    };
+    assert_eq!(pong, ClientEvent::Pong { connection: 0, payload: b"hb-1".to_vec() });
+}
+
+#[tokio::test]
+async fn subscribe_command_sends_subscribe_frame() {
+    let mut exchange = MockExchange::start(vec![vec![]]).await;
+    let source = BinanceSource::new(binance_config(&exchange.url(), None));
+    let (sinks, _md_rx) = sinks();
+    let (command_tx, mut command_rx) = mpsc::unbounded_channel();
+    let mut symbols = vec!["BTCUSDT".to_string()];
+    command_tx.send(MarketDataCommand::Subscribe("ethusdt".to_string())).unwrap();
+
+    let frame = tokio::select! {
+        frame = exchange.wait_for(|e| matches!(e, ClientEvent::Text { .. })) => frame,
+        exit = connect_and_stream(&source, &mut symbols, &mut command_rx, &sinks, || {}) => panic!("stream ended: {:?}", exit),
+    };
+    let ClientEvent::Text { text, .. } = frame else { unreachable!() };
+    let request: serde_json::Value = serde_json::from_str(&text).unwrap();
+    assert_eq!(request["method"], "SUBSCRIBE");
+    assert_eq!(request["params"], serde_json::json!(["ethusdt@trade", "ethusdt@bookTicker"]));
+    assert_eq!(symbols, vec!["BTCUSDT", "ETHUSDT"]);
+}
+
+#[tokio::test]
+async fn reconnects_after_abrupt_disconnect() {
+    let mut exchange = MockExchange::start(vec![vec![text(TRADE), Step::Disconnect], vec![text(BOOK_TICKER)]]).await;
+    let config = binance_config(&exchange.url(), None);
+    let (sinks, mut md_rx) = sinks();
+    let (_command_tx, command_rx) = mpsc::unbounded_channel();
+    let (status_tx, mut status_rx) = mpsc::unbounded_channel();
+    tokio::spawn(run_venue_feed(
+        Box::new(BinanceSource::new(config.clone())),
+        config.symbols.clone(),
+        command_rx,
+        sinks,
+        status_tx,
+        config.reconnect,
+    ));
+
+    assert!(recv(&mut md_rx).await.last_price.is_some());
+    assert!(recv(&mut md_rx).await.bid_price.is_some());
+
+    let statuses: Vec<ConnectionStatus> = std::iter::from_fn(|| status_rx.try_recv().ok()).map(|s| s.status).collect();
+    assert!(matches!(
+        statuses.as_slice(),
+        [
+            ConnectionStatus::Connecting,
+            ConnectionStatus::Connected,
+            ConnectionStatus::Reconnecting { attempt: 1, .. },
+            ConnectionStatus::Connecting,
+            ConnectionStatus::Connected,
+        ]
+    ), "unexpected statuses: {:?}", statuses);
+    exchange.wait_for(|e| matches!(e, ClientEvent::Connected { connection: 1, .. })).await;
+}
+
+#[tokio::test]
+async fn gives_up_after_max_retries() {
+    let exchange = MockExchange::start(vec![vec![Step::Disconnect]]).await;
+    let mut config = binance_config(&exchange.url(), None);
+    config.reconnect.max_retries = Some(1);
+    let (sinks, _md_rx) = sinks();
+    let (_command_tx, command_rx) = mpsc::unbounded_channel();
+    let (status_tx, mut status_rx) = mpsc::unbounded_channel();
+
+    let result = run_venue_feed(
+        Box::new(BinanceSource::new(config.clone())),
+        config.symbols.clone(),
+        command_rx,
+        sinks,
+        status_tx,
+        config.reconnect,
+    )
+    .await;
+    assert!(result.is_err());
+
+    let last = std::iter::from_fn(|| status_rx.try_recv().ok()).last().unwrap();
+    assert_eq!(last.status, ConnectionStatus::GaveUp { attempts: 1 });
+}
+
+#[tokio::test]
+async fn stops_when_the_consumer_goes_away() {
+    let mut exchange =
+        MockExchange::start(vec![vec![text(TRADE), Step::Sleep(Duration::from_millis(100)), text(TRADE)]]).await;
+    let config = binance_config(&exchange.url(), None);
+    let (sinks, mut md_rx) = sinks();
+    let (_command_tx, command_rx) = mpsc::unbounded_channel();
+    let (status_tx, mut status_rx) = mpsc::unbounded_channel();
+    let feed = tokio::spawn(run_venue_feed(
+        Box::new(BinanceSource::new(config.clone())),
+        config.symbols.clone(),
+        command_rx,
+        sinks,
+        status_tx,
+        config.reconnect,
+    ));
+
+    recv(&mut md_rx).await;
+    drop(md_rx);
+
+    let result = tokio::time::timeout(Duration::from_secs(5), feed).await.expect("feed did not stop");
+    assert!(result.unwrap().is_ok());
+    let last = std::iter::from_fn(|| status_rx.try_recv().ok()).last().unwrap();
+    assert_eq!(last.status, ConnectionStatus::Stopped);
+    exchange.wait_for(|e| matches!(e, ClientEvent::Closed { connection: 0 })).await;
+}
+
+#[test]
+fn subscribe_and_unsubscribe_commands_update_the_symbol_set() {
+    let mut symbols = vec!["BTCUSDT".to_string()];
+
+    assert_eq!(MarketDataCommand::Subscribe(" ethusdt ".to_string()).apply(&mut symbols), Some("ETHUSDT".to_string()));
+    assert_eq!(MarketDataCommand::Subscribe("BTCUSDT".to_string()).apply(&mut symbols), None);
+    assert_eq!(MarketDataCommand::Subscribe("  ".to_string()).apply(&mut symbols), None);
+    assert_eq!(symbols, vec!["BTCUSDT", "ETHUSDT"]);
+
+    assert_eq!(MarketDataCommand::Unsubscribe("btcusdt".to_string()).apply(&mut symbols), Some("BTCUSDT".to_string()));
+    assert_eq!(MarketDataCommand::Unsubscribe("SOLUSDT".to_string()).apply(&mut symbols), None);
+    assert_eq!(symbols, vec!["ETHUSDT"]);
+}
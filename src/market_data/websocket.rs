@@ -1,31 +1,60 @@
 use tungstenite::Message;
-use url::Url;
 use futures_util::{StreamExt, SinkExt};
+use std::collections::HashSet;
 use tokio::sync::mpsc;
-use tracing::{info, error, debug};
-use serde_json::Value;
+use tracing::{info, error, debug, warn};
 use anyhow::{Result, anyhow};
+use chrono::Utc;
 use tokio_tungstenite;
 
-use crate::data_model::MarketDataUpdate;
+use crate::market_data::{FeedSinks, MarketDataCommand};
+use crate::market_data::order_book::{DepthSnapshot, DiffOutcome};
+use crate::market_data::source::{MarketDataSource, MarketEvent};
 
+/// Why a connected stream stopped delivering messages.
+#[derive(Debug, Clone, PartialEq)]
+pub enum StreamExit {
+    ServerClosed,
+    ConnectionLost(String),
+    ChannelClosed,
+}
+
+/// Streams one connection to `source` until it drops, feeding depth diffs
+/// into the sinks' books and everything else to their `md_tx`.
 pub async fn connect_and_stream(
-    url_str: String,
-    md_tx: mpsc::UnboundedSender<MarketDataUpdate>,
-) -> Result<()> {
-    let url = Url::parse(&url_str)?;
-    info!("Connecting to WebSocket: {}", url_str);
+    source: &dyn MarketDataSource,
+    symbols: &mut Vec<String>,
+    commands: &mut mpsc::UnboundedReceiver<MarketDataCommand>,
+    sinks: &FeedSinks,
+    on_connected: impl FnOnce(),
+) -> Result<StreamExit> {
+    let venue = source.venue();
+    let books = &sinks.books;
+    let url = source.connect_url(symbols)?;
+    info!("Connecting to {} WebSocket: {}", venue, url);
 
     let (ws_stream, _) = tokio_tungstenite::connect_async(url).await
         .map_err(|e| anyhow!("Failed to connect to WebSocket: {}", e))?;
 
-    info!("WebSocket connected.");
+    info!("{} WebSocket connected.", venue);
+    on_connected();
 
     let (mut write, mut read) = ws_stream.split();
+    for frame in source.initial_frames(symbols) {
+        write.send(Message::Text(frame)).await
+            .map_err(|e| anyhow!("Failed to send subscription: {}", e))?;
+    }
+    info!("Subscribed to {:?} on {}", symbols, venue);
+
+    // Diffs missed while disconnected make every book stale. Venues without
+    // a depth feed get no book at all.
+    for symbol in symbols.iter().filter(|s| source.depth_snapshot(s).is_some()) {
+        books.write(symbol, |book| book.reset());
+    }
 
     let (tx_ws_out, mut rx_ws_out) = mpsc::unbounded_channel::<Message>();
 
-    tokio::spawn(async move {
+    let writer = tokio::spawn(async move {
         while let Some(message) = rx_ws_out.recv().await {
             if let Err(e) = write.send(message).await {
                 error!("Failed to send message to WebSocket: {}", e);
@@ -35,7 +64,7 @@ pub async fn connect_and_stream(
     });
 
     let tx_ws_out_ping = tx_ws_out.clone();
-    tokio::spawn(async move {
+    let pinger = tokio::spawn(async move {
         loop {
             tokio::time::sleep(tokio::time::Duration::from_secs(30)).await;
             if let Err(e) = tx_ws_out_ping.send(Message::Ping(vec![])) {
@@ -47,27 +76,119 @@ pub async fn connect_and_stream(
     });
 
 
-    while let Some(msg_res) = read.next().await {
+    let (snapshot_tx, mut snapshot_rx) = mpsc::unbounded_channel::<(String, Result<DepthSnapshot>)>();
+    let mut snapshots_in_flight = HashSet::new();
+    let mut request_id = 0;
+
+    let mut exit = StreamExit::ConnectionLost("stream ended".to_string());
+
+    loop {
+        let msg_res = tokio::select! {
+            msg_res = read.next() => match msg_res {
+                Some(msg_res) => msg_res,
+                None => break,
+            },
+            Some((symbol, result)) = snapshot_rx.recv() => {
+                snapshots_in_flight.remove(&symbol);
+                match result {
+                    Ok(snapshot) => {
+                        let synced = books.write(&symbol, |book| book.apply_snapshot(snapshot));
+                        match synced {
+                            Ok(()) => {
+                                info!("Order book for {} synchronized.", symbol);
+                                if !publish_quote(sinks, &symbol) {
+                                    exit = StreamExit::ChannelClosed;
+                                    break;
+                                }
+                            }
+                            Err(e) => {
+                                warn!("Order book resync needed: {}", e);
+                                request_snapshot(source, &symbol, &mut snapshots_in_flight, &snapshot_tx);
+                            }
+                        }
+                    }
+                    // Retried on the next diff for this symbol.
+                    Err(e) => error!("Depth snapshot for {} failed: {}", symbol, e),
+                }
+                continue;
+            }
+            Some(command) = commands.recv() => {
+                if let Some(symbol) = command.apply(symbols) {
+                    let subscribe = matches!(command, MarketDataCommand::Subscribe(_));
+                    if !subscribe {
+                        books.remove(&symbol);
+                    }
+                    request_id += 1;
+                    info!("{:?} on {} (request {})", command, venue, request_id);
+                    let frame = source.subscription_frame(&symbol, subscribe, request_id);
+                    if let Err(e) = tx_ws_out.send(Message::Text(frame)) {
+                        exit = StreamExit::ConnectionLost(e.to_string());
+                        break;
+                    }
+                }
+                continue;
+            }
+        };
         match msg_res {
             Ok(msg) => {
                 match msg {
                     Message::Text(text) => {
-                        match parse_binance_agg_trade(&text) {
-                            Ok(update) => {
-                                if let Err(e) = md_tx.send(update) {
-                                    error!("Failed to send market data update to UI: {}", e);
-                                    break;
-                                }
-                            }
+                        let received_at = Utc::now();
+                        let events = match source.parse(&text) {
+                            Ok(events) => events,
                             Err(e) => {
-                                error!("Failed to parse market data JSON: {} - {}", e, text);
+                                sinks.recorder.record_frame(venue, None, &text, received_at);
+                                error!("Failed to parse {} market data: {} - {}", venue, e, text);
+                                continue;
+                            }
+                        };
+                        let symbol = events.first().map(MarketEvent::symbol);
+                        sinks.recorder.record_frame(venue, symbol, &text, received_at);
+                        for event in events {
+                            match event {
+                                MarketEvent::Update(update) => {
+                                    if !sinks.publish(update) {
+                                        error!("Failed to send market data update to UI: channel closed");
+                                        exit = StreamExit::ChannelClosed;
+                                        break;
+                                    }
+                                }
+                                MarketEvent::Depth(diff) => {
+                                    let symbol = diff.symbol.clone();
+                                    let before = books.read(&symbol, |book| (book.best_bid(), book.best_ask()));
+                                    match books.write(&symbol, |book| book.apply_diff(diff)) {
+                                        Ok(DiffOutcome::Applied) => {
+                                            let after = books.read(&symbol, |book| (book.best_bid(), book.best_ask()));
+                                            if before != after && !publish_quote(sinks, &symbol) {
+                                                exit = StreamExit::ChannelClosed;
+                                                break;
+                                            }
+                                        }
+                                        Ok(DiffOutcome::Buffered) => {
+                                            request_snapshot(source, &symbol, &mut snapshots_in_flight, &snapshot_tx);
+                                        }
+                                        Ok(DiffOutcome::Stale) => {}
+                                        Err(e) => {
+                                            warn!("Order book resync needed: {}", e);
+                                            request_snapshot(source, &symbol, &mut snapshots_in_flight, &snapshot_tx);
+                                        }
+                                    }
+                                }
+                                MarketEvent::Candle { symbol, interval, closed, candle } => {
+                                    debug!("Kline {} {} (closed: {}): O {} H {} L {} C {} V {} at {}",
+                                        symbol, interval, closed, candle.open, candle.high, candle.low, candle.close, candle.volume, candle.timestamp);
+                                }
                             }
                         }
+                        if exit == StreamExit::ChannelClosed {
+                            break;
+                        }
                     },
                     Message::Ping(p) => {
                         debug!("Received Ping: {:?}", p);
                         if let Err(e) = tx_ws_out.send(Message::Pong(p)) {
                             error!("Failed to send Pong via channel: {}", e);
+                            exit = StreamExit::ConnectionLost(e.to_string());
                             break; 
                         }
                     },
@@ -79,6 +200,7 @@ pub async fn connect_and_stream(
                     },
                     Message::Close(cf) => {
                         info!("WebSocket closed: {:?}", cf);
+                        exit = StreamExit::ServerClosed;
                         break;
                     },
                     _ => {}
@@ -86,39 +208,43 @@ pub async fn connect_and_stream(
             },
             Err(e) => {
                 error!("WebSocket error: {}", e);
+                exit = StreamExit::ConnectionLost(e.to_string());
                 break;
             }
         }
     }
 
-    error!("WebSocket stream ended.");
-    Ok(())
+    pinger.abort();
+    writer.abort();
+
+    error!("WebSocket stream ended: {:?}", exit);
+    Ok(exit)
 }
 
-fn parse_binance_agg_trade(json_str: &str) -> Result<MarketDataUpdate> {
-    let v: Value = serde_json::from_str(json_str)?;
-
-    let event_type = v["e"].as_str().unwrap_or_default();
-
-    if event_type == "aggTrade" {
-        let symbol = v["s"].as_str().ok_or_else(|| anyhow!("Missing symbol"))?.to_string();
-        let price = v["p"].as_str().unwrap_or("0.0").parse::<f64>()?;
-        let quantity = v["q"].as_str().unwrap_or("0.0").parse::<f64>()?;
-        let timestamp_ms = v["E"].as_u64().ok_or_else(|| anyhow!("Missing timestamp"))?;
-        let timestamp = chrono::DateTime::from_timestamp_millis(timestamp_ms as i64)
-                                .ok_or_else(|| anyhow!("Invalid timestamp"))?;
-
-        Ok(MarketDataUpdate {
-            symbol,
-            timestamp,
-            bid_price: None,
-            bid_quantity: None,
-            ask_price: None,
-            ask_quantity: None,
-            last_price: Some(price),
-            last_quantity: Some(quantity),
-        })
-    } else {
-        Err(anyhow!("Unsupported event type: {}", event_type))
+/// Sends the book's top of book downstream. Returns false once the consumer
+/// is gone.
+fn publish_quote(sinks: &FeedSinks, symbol: &str) -> bool {
+    match sinks.books.read(symbol, |book| book.quote()).flatten() {
+        Some(update) => sinks.publish(update),
+        None => true,
     }
-}
\ No newline at end of file
+}
+
+fn request_snapshot(
+    source: &dyn MarketDataSource,
+    symbol: &str,
+    in_flight: &mut HashSet<String>,
+    snapshot_tx: &mpsc::UnboundedSender<(String, Result<DepthSnapshot>)>,
+) {
+    if in_flight.contains(symbol) {
+        return;
+    }
+    let Some(snapshot) = source.depth_snapshot(symbol) else {
+        return;
+    };
+    in_flight.insert(symbol.to_string());
+    let (symbol, snapshot_tx) = (symbol.to_string(), snapshot_tx.clone());
+    tokio::spawn(async move {
+        let _ = snapshot_tx.send((symbol, snapshot.await));
+    });
+}
@@ -0,0 +1,76 @@
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+
+use crate::oms::risk::DayStart;
+use crate::oms::{OmsCommand, OmsUpdate};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum JournalRecord {
+    Command(OmsCommand),
+    Update(OmsUpdate),
+    /// Daily loss baseline, written when a new UTC day starts.
+    DayStart(DayStart),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JournalEntry {
+    pub timestamp: DateTime<Utc>,
+    pub record: JournalRecord,
+}
+
+/// Append-only JSONL log of everything the OMS receives and publishes. Each
+/// entry is flushed and fsync'd before `append` returns.
+pub struct Journal {
+    path: PathBuf,
+    file: File,
+}
+
+impl Journal {
+    /// Opens (or creates) the journal and returns the entries already in it.
+    /// A torn final line from a crash mid-write is skipped.
+    pub fn open(path: &Path) -> Result<(Self, Vec<JournalEntry>)> {
+        let entries = if path.exists() { Self::read_entries(path)? } else { Vec::new() };
+
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .with_context(|| format!("Failed to open OMS journal {}", path.display()))?;
+
+        info!("OMS journal {} opened with {} entries.", path.display(), entries.len());
+        Ok((Self { path: path.to_path_buf(), file }, entries))
+    }
+
+    fn read_entries(path: &Path) -> Result<Vec<JournalEntry>> {
+        let reader = BufReader::new(File::open(path)?);
+        let mut entries = Vec::new();
+        for (i, line) in reader.lines().enumerate() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            match serde_json::from_str::<JournalEntry>(&line) {
+                Ok(entry) => entries.push(entry),
+                Err(e) => warn!("Skipping unreadable OMS journal line {} in {}: {}", i + 1, path.display(), e),
+            }
+        }
+        Ok(entries)
+    }
+
+    pub fn append(&self, record: JournalRecord) -> Result<()> {
+        let entry = JournalEntry { timestamp: Utc::now(), record };
+        let mut line = serde_json::to_string(&entry)?;
+        line.push('\n');
+
+        let mut file = &self.file;
+        file.write_all(line.as_bytes())
+            .and_then(|_| file.sync_data())
+            .with_context(|| format!("Failed to write OMS journal {}", self.path.display()))
+    }
+}
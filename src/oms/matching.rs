@@ -0,0 +1,431 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+use crate::config::SimulatorConfig;
+use crate::data_model::{MarketDataUpdate, Order, OrderType, Side, TimeInForce, QTY_EPSILON};
+use crate::oms::{Fill, OrderState};
+
+/// Output of the simulated exchange, in the order it happened.
+#[derive(Debug, Clone)]
+pub enum MatchEvent {
+    Fill(Fill),
+    StateChange(Uuid, OrderState),
+}
+
+#[derive(Debug, Clone, Default)]
+struct Quote {
+    bid: Option<(f64, Option<f64>)>,
+    ask: Option<(f64, Option<f64>)>,
+    last: Option<(f64, Option<f64>)>,
+}
+
+impl Quote {
+    fn apply(&mut self, update: &MarketDataUpdate) {
+        if let Some(price) = update.bid_price {
+            self.bid = Some((price, update.bid_quantity));
+        }
+        if let Some(price) = update.ask_price {
+            self.ask = Some((price, update.ask_quantity));
+        }
+        if let Some(price) = update.last_price {
+            self.last = Some((price, update.last_quantity));
+        }
+    }
+
+    /// Price and available size a taker on `side` would trade against.
+    fn touch(&self, side: &Side) -> Option<(f64, Option<f64>)> {
+        match side {
+            Side::Buy => self.ask.or(self.last),
+            Side::Sell => self.bid.or(self.last),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct RestingOrder {
+    order: Order,
+    remaining: f64,
+    filled: f64,
+}
+
+/// Paper-trading exchange. Keeps a per-symbol book of working orders and
+/// fills them against the live quote and trade stream:
+/// - market orders take the touch (ask for buys, bid for sells, last trade as
+///   fallback) with `slippage_bps` applied against us;
+/// - limit orders fill only once the touch or a trade crosses their price.
+///   Arriving or amended orders check the bid and ask only; trades count
+///   only for orders already resting when they print;
+/// - each update offers at most the quoted or traded size, so large orders
+///   fill in parts;
+/// - IOC orders cancel whatever they could not fill on arrival. Market
+///   orders do too, whatever their time in force, so they never rest
+///   without a price limit.
+pub struct MatchingEngine {
+    config: SimulatorConfig,
+    quotes: HashMap<String, Quote>,
+    books: HashMap<String, Vec<RestingOrder>>,
+}
+
+impl MatchingEngine {
+    pub fn new(config: SimulatorConfig) -> Self {
+        Self {
+            config,
+            quotes: HashMap::new(),
+            books: HashMap::new(),
+        }
+    }
+
+    pub fn submit(&mut self, order: Order, now: DateTime<Utc>) -> Vec<MatchEvent> {
+        let mut events = Vec::new();
+        if order.order_type == OrderType::Limit && order.price.is_none() {
+            events.push(MatchEvent::StateChange(order.order_id, OrderState::Rejected));
+            return events;
+        }
+
+        let quote = self.quotes.get(&order.symbol).cloned().unwrap_or_default();
+        let mut resting = RestingOrder { remaining: order.quantity, filled: 0.0, order };
+        let mut liquidity = None;
+        Self::fill_against(&self.config, &mut resting, &quote, false, &mut liquidity, now, &mut events);
+
+        if resting.remaining <= QTY_EPSILON {
+            events.push(MatchEvent::StateChange(resting.order.order_id, OrderState::Filled));
+        } else if resting.order.tif == TimeInForce::IOC || resting.order.order_type == OrderType::Market {
+            events.push(MatchEvent::StateChange(resting.order.order_id, OrderState::Canceled));
+        } else {
+            let state = if resting.filled > 0.0 { OrderState::PartialFill } else { OrderState::Open };
+            events.push(MatchEvent::StateChange(resting.order.order_id, state));
+            self.rest(resting);
+        }
+        events
+    }
+
+    pub fn on_market_data(&mut self, update: &MarketDataUpdate) -> Vec<MatchEvent> {
+        self.quotes.entry(update.symbol.clone()).or_default().apply(update);
+
+        // Working orders only trade against what this update offers, so a
+        // stale quote is never matched twice.
+        let mut quote = Quote::default();
+        quote.apply(update);
+
+        let mut events = Vec::new();
+        let Some(book) = self.books.get_mut(&update.symbol) else {
+            return events;
+        };
+
+        // Liquidity is shared across the book within a single update.
+        let mut buy_liquidity = None;
+        let mut sell_liquidity = None;
+        for resting in book.iter_mut() {
+            let liquidity = match resting.order.side {
+                Side::Buy => &mut buy_liquidity,
+                Side::Sell => &mut sell_liquidity,
+            };
+            let before = resting.filled;
+            Self::fill_against(&self.config, resting, &quote, true, liquidity, update.timestamp, &mut events);
+            if resting.filled > before {
+                let state = if resting.remaining <= QTY_EPSILON { OrderState::Filled } else { OrderState::PartialFill };
+                events.push(MatchEvent::StateChange(resting.order.order_id, state));
+            }
+        }
+        book.retain(|r| r.remaining > QTY_EPSILON);
+        events
+    }
+
+    /// Removes a working order from the book. Returns the order and its
+    /// unfilled quantity if it was still resting.
+    pub fn cancel(&mut self, order_id: Uuid) -> Option<(Order, f64)> {
+        let book = self.books.values_mut().find(|b| b.iter().any(|r| r.order.order_id == order_id))?;
+        let idx = book.iter().position(|r| r.order.order_id == order_id)?;
+        let resting = book.remove(idx);
+        Some((resting.order, resting.remaining))
+    }
+
+    /// Amends a working order's price and/or total quantity. The amended
+    /// order loses its time priority and is matched again immediately, so a
+    /// price change can make it marketable. Returns `None` if the order is
+    /// not resting or the new quantity does not exceed what already filled.
+    pub fn replace(
+        &mut self,
+        order_id: Uuid,
+        new_price: Option<f64>,
+        new_quantity: Option<f64>,
+        now: DateTime<Utc>,
+    ) -> Option<Vec<MatchEvent>> {
+        let book = self.books.values_mut().find(|b| b.iter().any(|r| r.order.order_id == order_id))?;
+        let idx = book.iter().position(|r| r.order.order_id == order_id)?;
+        let filled = book[idx].filled;
+        if new_quantity.is_some_and(|q| q <= filled + QTY_EPSILON) {
+            return None;
+        }
+
+        let mut resting = book.remove(idx);
+        if let Some(price) = new_price {
+            resting.order.price = Some(price);
+        }
+        if let Some(quantity) = new_quantity {
+            resting.order.quantity = quantity;
+            resting.remaining = quantity - filled;
+        }
+
+        let mut events = Vec::new();
+        let quote = self.quotes.get(&resting.order.symbol).cloned().unwrap_or_default();
+        let mut liquidity = None;
+        Self::fill_against(&self.config, &mut resting, &quote, false, &mut liquidity, now, &mut events);
+
+        if resting.remaining <= QTY_EPSILON {
+            events.push(MatchEvent::StateChange(order_id, OrderState::Filled));
+        } else {
+            let state = if resting.filled > 0.0 { OrderState::PartialFill } else { OrderState::Open };
+            events.push(MatchEvent::StateChange(order_id, state));
+            self.rest(resting);
+        }
+        Some(events)
+    }
+
+    /// Puts a working order recovered from the journal back on the book
+    /// without matching it.
+    pub fn restore(&mut self, order: Order, filled: f64) {
+        let remaining = (order.quantity - filled).max(0.0);
+        if remaining > QTY_EPSILON {
+            self.rest(RestingOrder { order, remaining, filled });
+        }
+    }
+
+    /// Last traded price, falling back to the mid of the latest quote.
+    pub fn last_price(&self, symbol: &str) -> Option<f64> {
+        let quote = self.quotes.get(symbol)?;
+        match (quote.last, quote.bid, quote.ask) {
+            (Some((last, _)), _, _) => Some(last),
+            (None, Some((bid, _)), Some((ask, _))) => Some((bid + ask) / 2.0),
+            _ => None,
+        }
+    }
+
+    fn rest(&mut self, resting: RestingOrder) {
+        let book = self.books.entry(resting.order.symbol.clone()).or_default();
+        book.push(resting);
+        // Price-time priority: best price first, stable sort keeps arrival order.
+        book.sort_by(|a, b| {
+            let key = |r: &RestingOrder| match (&r.order.order_type, &r.order.side, r.order.price) {
+                (OrderType::Market, _, _) => f64::NEG_INFINITY,
+                (_, Side::Buy, Some(p)) => -p,
+                (_, Side::Sell, Some(p)) => p,
+                (_, _, None) => f64::INFINITY,
+            };
+            key(a).total_cmp(&key(b))
+        });
+    }
+
+    fn fill_against(
+        config: &SimulatorConfig,
+        resting: &mut RestingOrder,
+        quote: &Quote,
+        resting_on_trades: bool,
+        liquidity: &mut Option<f64>,
+        now: DateTime<Utc>,
+        events: &mut Vec<MatchEvent>,
+    ) {
+        let order = &resting.order;
+        let Some((price, size)) = Self::execution(config, order, quote, resting_on_trades) else {
+            return;
+        };
+
+        let available = match (*liquidity, size) {
+            (Some(left), _) => left,
+            (None, Some(size)) if config.partial_fills => size,
+            _ => f64::INFINITY,
+        };
+        let quantity = resting.remaining.min(available);
+        if quantity <= QTY_EPSILON {
+            return;
+        }
+        if available.is_finite() {
+            *liquidity = Some(available - quantity);
+        }
+
+        resting.remaining -= quantity;
+        resting.filled += quantity;
+        events.push(MatchEvent::Fill(Fill {
+            order_id: order.order_id,
+            symbol: order.symbol.clone(),
+            side: order.side.clone(),
+            quantity,
+            price,
+            timestamp: now,
+        }));
+    }
+
+    /// Execution price and offered size for `order` against `quote`, if it
+    /// is marketable. A limit order only trades against the last print when
+    /// `resting_on_trades`, i.e. it was on the book before that trade.
+    fn execution(config: &SimulatorConfig, order: &Order, quote: &Quote, resting_on_trades: bool) -> Option<(f64, Option<f64>)> {
+        match order.order_type {
+            OrderType::Market => {
+                let (touch, size) = quote.touch(&order.side)?;
+                let slip = touch * config.slippage_bps / 10_000.0;
+                let price = match order.side {
+                    Side::Buy => touch + slip,
+                    Side::Sell => touch - slip,
+                };
+                Some((price, size))
+            }
+            OrderType::Limit => {
+                let limit = order.price?;
+                let crosses = |p: f64| match order.side {
+                    Side::Buy => p <= limit,
+                    Side::Sell => p >= limit,
+                };
+                let book_side = match order.side {
+                    Side::Buy => quote.ask,
+                    Side::Sell => quote.bid,
+                };
+                if let Some((touch, size)) = book_side.filter(|(p, _)| crosses(*p)) {
+                    Some((touch, size))
+                } else if resting_on_trades {
+                    quote.last.filter(|(p, _)| crosses(*p)).map(|(_, size)| (limit, size))
+                } else {
+                    None
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn engine() -> MatchingEngine {
+        MatchingEngine::new(SimulatorConfig { slippage_bps: 0.0, partial_fills: true })
+    }
+
+    fn update(bid: Option<(f64, f64)>, ask: Option<(f64, f64)>, last: Option<(f64, f64)>) -> MarketDataUpdate {
+        MarketDataUpdate {
+            symbol: "BTCUSDT".to_string(),
+            timestamp: Utc::now(),
+            bid_price: bid.map(|b| b.0),
+            bid_quantity: bid.map(|b| b.1),
+            ask_price: ask.map(|a| a.0),
+            ask_quantity: ask.map(|a| a.1),
+            last_price: last.map(|l| l.0),
+            last_quantity: last.map(|l| l.1),
+            aggressor: None,
+        }
+    }
+
+    fn order(side: Side, order_type: OrderType, quantity: f64, price: Option<f64>, tif: TimeInForce) -> Order {
+        Order::new("BTCUSDT".to_string(), side, order_type, quantity, price, tif)
+    }
+
+    fn fills(events: &[MatchEvent]) -> Vec<(Uuid, f64, f64)> {
+        events
+            .iter()
+            .filter_map(|e| match e {
+                MatchEvent::Fill(f) => Some((f.order_id, f.quantity, f.price)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn last_state(events: &[MatchEvent], order_id: Uuid) -> Option<OrderState> {
+        events.iter().rev().find_map(|e| match e {
+            MatchEvent::StateChange(id, state) if *id == order_id => Some(state.clone()),
+            _ => None,
+        })
+    }
+
+    #[test]
+    fn limit_order_rests_then_fills_in_parts() {
+        let mut engine = engine();
+        engine.on_market_data(&update(Some((99.0, 5.0)), Some((101.0, 5.0)), None));
+        let buy = order(Side::Buy, OrderType::Limit, 3.0, Some(100.0), TimeInForce::GTC);
+        let id = buy.order_id;
+        let events = engine.submit(buy, Utc::now());
+        assert!(fills(&events).is_empty());
+        assert_eq!(last_state(&events, id), Some(OrderState::Open));
+
+        let events = engine.on_market_data(&update(None, Some((100.0, 1.0)), None));
+        assert_eq!(fills(&events), vec![(id, 1.0, 100.0)]);
+        assert_eq!(last_state(&events, id), Some(OrderState::PartialFill));
+
+        let events = engine.on_market_data(&update(None, None, Some((99.5, 5.0))));
+        assert_eq!(fills(&events), vec![(id, 2.0, 100.0)]);
+        assert_eq!(last_state(&events, id), Some(OrderState::Filled));
+        assert!(engine.cancel(id).is_none());
+    }
+
+    #[test]
+    fn arriving_limit_order_ignores_an_old_trade_print() {
+        let mut engine = engine();
+        engine.on_market_data(&update(Some((99.9, 1.0)), Some((100.1, 1.0)), Some((99.9, 1.0))));
+        let buy = order(Side::Buy, OrderType::Limit, 1.0, Some(100.0), TimeInForce::GTC);
+        let id = buy.order_id;
+        let events = engine.submit(buy, Utc::now());
+        assert!(fills(&events).is_empty());
+        assert_eq!(last_state(&events, id), Some(OrderState::Open));
+
+        let events = engine.replace(id, Some(100.05), None, Utc::now()).unwrap();
+        assert!(fills(&events).is_empty());
+
+        // A trade printing through it once it rests does fill it.
+        let events = engine.on_market_data(&update(None, None, Some((100.0, 1.0))));
+        assert_eq!(fills(&events), vec![(id, 1.0, 100.05)]);
+    }
+
+    #[test]
+    fn resting_orders_share_the_liquidity_of_one_update() {
+        let mut engine = engine();
+        let first = order(Side::Buy, OrderType::Limit, 2.0, Some(101.0), TimeInForce::GTC);
+        let second = order(Side::Buy, OrderType::Limit, 2.0, Some(100.0), TimeInForce::GTC);
+        let (first_id, second_id) = (first.order_id, second.order_id);
+        engine.submit(second, Utc::now());
+        engine.submit(first, Utc::now());
+
+        let events = engine.on_market_data(&update(None, Some((100.0, 3.0)), None));
+        // Better-priced order first, and only the 3.0 offered is handed out.
+        assert_eq!(fills(&events), vec![(first_id, 2.0, 100.0), (second_id, 1.0, 100.0)]);
+        assert_eq!(last_state(&events, second_id), Some(OrderState::PartialFill));
+        let (_, remaining) = engine.cancel(second_id).unwrap();
+        assert_eq!(remaining, 1.0);
+    }
+
+    #[test]
+    fn market_order_cancels_what_the_touch_cannot_fill() {
+        let mut engine = MatchingEngine::new(SimulatorConfig { slippage_bps: 10.0, partial_fills: true });
+        engine.on_market_data(&update(Some((99.0, 1.0)), Some((100.0, 1.5)), None));
+        let buy = order(Side::Buy, OrderType::Market, 4.0, None, TimeInForce::GTC);
+        let id = buy.order_id;
+        let events = engine.submit(buy, Utc::now());
+        assert_eq!(fills(&events), vec![(id, 1.5, 100.1)]);
+        assert_eq!(last_state(&events, id), Some(OrderState::Canceled));
+        assert!(engine.cancel(id).is_none());
+    }
+
+    #[test]
+    fn ioc_limit_cancels_when_not_marketable() {
+        let mut engine = engine();
+        engine.on_market_data(&update(Some((99.0, 1.0)), Some((101.0, 1.0)), None));
+        let sell = order(Side::Sell, OrderType::Limit, 1.0, Some(100.0), TimeInForce::IOC);
+        let id = sell.order_id;
+        let events = engine.submit(sell, Utc::now());
+        assert!(fills(&events).is_empty());
+        assert_eq!(last_state(&events, id), Some(OrderState::Canceled));
+    }
+
+    #[test]
+    fn replace_rejects_a_quantity_at_or_below_the_filled_amount() {
+        let mut engine = engine();
+        let buy = order(Side::Buy, OrderType::Limit, 3.0, Some(100.0), TimeInForce::GTC);
+        let id = buy.order_id;
+        engine.submit(buy, Utc::now());
+        engine.on_market_data(&update(None, Some((100.0, 2.0)), None));
+
+        assert!(engine.replace(id, None, Some(2.0), Utc::now()).is_none());
+        let events = engine.replace(id, Some(99.0), Some(4.0), Utc::now()).unwrap();
+        assert_eq!(last_state(&events, id), Some(OrderState::PartialFill));
+        let (order, remaining) = engine.cancel(id).unwrap();
+        assert_eq!((order.price, remaining), (Some(99.0), 2.0));
+    }
+}
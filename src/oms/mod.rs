@@ -1,98 +1,810 @@
+pub mod journal;
+pub mod matching;
 pub mod order;
 pub mod position;
+pub mod risk;
 
 use tokio::sync::mpsc;
-use tracing::{info, error}; 
-use std::collections::HashMap;
-use std::sync::Arc;
-use parking_lot::RwLock;
+use tracing::{info, debug, error, warn};
+use std::collections::{HashMap, HashSet};
 use anyhow::Result;
+use serde::{Deserialize, Serialize};
 
-use crate::data_model::Order;
+use crate::config::OmsConfig;
+use crate::data_model::{MarketDataUpdate, Order, OrderType, Side};
 pub use crate::oms::order::{OrderState, FullOrder};
+use crate::oms::journal::{Journal, JournalEntry, JournalRecord};
+use crate::oms::matching::{MatchEvent, MatchingEngine};
 use crate::oms::position::PositionManager;
 use crate::oms::position::Position;
+use crate::oms::risk::{RiskContext, RiskManager};
 
-#[derive(Debug, Clone)]
+/// Requests into the OMS from the UI and strategies.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum OmsCommand {
+    New(Order),
+    Cancel {
+        order_id: uuid::Uuid,
+    },
+    /// Amend the limit price and/or total quantity of a working order.
+    Replace {
+        order_id: uuid::Uuid,
+        new_price: Option<f64>,
+        new_quantity: Option<f64>,
+    },
+    /// Cancel every working order and reject new ones until `Rearm`.
+    KillSwitch {
+        triggered_by: String,
+        reason: String,
+    },
+    Rearm {
+        triggered_by: String,
+    },
+}
+
+/// Audit record for the kill switch being engaged or re-armed.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KillSwitchEvent {
+    pub engaged: bool,
+    pub triggered_by: String,
+    pub reason: Option<String>,
+    pub canceled_orders: Vec<uuid::Uuid>,
+    pub timestamp: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum OmsUpdate {
-    OrderCreated(Order), 
+    OrderCreated(Order),
     OrderStateChange {
-        order_id: uuid::Uuid, 
+        order_id: uuid::Uuid,
         new_state: OrderState,
         timestamp: chrono::DateTime<chrono::Utc>,
     },
+    OrderReplaced {
+        order_id: uuid::Uuid,
+        price: Option<f64>,
+        quantity: f64,
+        timestamp: chrono::DateTime<chrono::Utc>,
+    },
+    /// Sent alongside the `Rejected` state change with the reason.
+    OrderRejected {
+        order_id: uuid::Uuid,
+        reason: String,
+    },
+    /// A cancel or replace request that the OMS refused; the order is unchanged.
+    RequestRejected {
+        order_id: uuid::Uuid,
+        reason: String,
+    },
+    Fill(Fill),
     PositionUpdate(Position),
+    KillSwitch(KillSwitchEvent),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Fill {
+    pub order_id: uuid::Uuid,
+    pub symbol: String,
+    pub side: Side,
+    pub quantity: f64,
+    pub price: f64,
+    pub timestamp: chrono::DateTime<chrono::Utc>,
+}
+
+/// Order and position book-keeping on top of the simulated exchange. All
+/// state is owned by the `run_oms` task; every change is published as an
+/// `OmsUpdate`.
+pub struct Oms {
+    orders: HashMap<uuid::Uuid, FullOrder>,
+    positions: PositionManager,
+    matching: MatchingEngine,
+    risk: RiskManager,
+    halted: bool,
+    journal: Option<Journal>,
+    updates_tx: mpsc::UnboundedSender<OmsUpdate>,
+    // Marks arrive tick by tick; PnL updates are published at most once per interval.
+    marked_symbols: HashSet<String>,
+    /// Cancels and amends that were pending when the process stopped, issued
+    /// again by `resume_interrupted`.
+    interrupted: Vec<OmsCommand>,
+    /// Orders submitted but never acknowledged before the process stopped,
+    /// rejected by `resume_interrupted`.
+    unacknowledged: Vec<uuid::Uuid>,
+}
+
+impl Oms {
+    pub fn new(config: OmsConfig, updates_tx: mpsc::UnboundedSender<OmsUpdate>) -> Self {
+        Self {
+            orders: HashMap::new(),
+            positions: PositionManager::new(),
+            matching: MatchingEngine::new(config.simulator),
+            risk: RiskManager::new(config.risk),
+            halted: false,
+            journal: None,
+            updates_tx,
+            marked_symbols: HashSet::new(),
+            interrupted: Vec::new(),
+            unacknowledged: Vec::new(),
+        }
+    }
+
+    /// Journals every record from here on. Attach after `recover` so the
+    /// replayed history is not written twice.
+    pub fn attach_journal(&mut self, journal: Journal) {
+        self.journal = Some(journal);
+    }
+
+    fn record(&self, record: JournalRecord) {
+        if let Some(journal) = &self.journal {
+            if let Err(e) = journal.append(record) {
+                error!("{:#}", e);
+            }
+        }
+    }
+
+    fn publish(&self, update: OmsUpdate) {
+        self.record(JournalRecord::Update(update.clone()));
+        self.notify(update);
+    }
+
+    /// Sends an update to subscribers without journaling it.
+    fn notify(&self, update: OmsUpdate) {
+        if let Err(e) = self.updates_tx.send(update) {
+            error!("Failed to publish OMS update: {}", e);
+        }
+    }
+
+    /// Rebuilds orders, positions and the kill switch state from journaled
+    /// updates, puts working orders back on the simulated book and sends a
+    /// snapshot to subscribers.
+    pub fn recover(&mut self, entries: Vec<JournalEntry>) {
+        let mut replayed = 0;
+        let mut last_replace = HashMap::new();
+        for entry in entries {
+            match entry.record {
+                JournalRecord::Update(update) => {
+                    self.replay(update);
+                    replayed += 1;
+                }
+                JournalRecord::DayStart(start) => self.risk.restore_day(start),
+                JournalRecord::Command(command @ OmsCommand::Replace { order_id, .. }) => {
+                    last_replace.insert(order_id, command);
+                }
+                JournalRecord::Command(_) => {}
+            }
+        }
+
+        let mut orders: Vec<&FullOrder> = self.orders.values().collect();
+        orders.sort_by_key(|o| o.order.placed_at);
+        for order in &orders {
+            let order_id = order.order.order_id;
+            match order.current_state {
+                OrderState::Open | OrderState::PartialFill => {
+                    self.matching.restore(order.order.clone(), order.filled_quantity)
+                }
+                // Still resting at its last acknowledged price and quantity.
+                OrderState::PendingCancel => {
+                    self.matching.restore(order.order.clone(), order.filled_quantity);
+                    self.interrupted.push(OmsCommand::Cancel { order_id });
+                }
+                OrderState::PendingReplace => {
+                    self.matching.restore(order.order.clone(), order.filled_quantity);
+                    match last_replace.remove(&order_id) {
+                        Some(command) => self.interrupted.push(command),
+                        None => warn!("No journaled replace for pending order {}; it stays at its last price", order_id),
+                    }
+                }
+                // In flight when the process died; the exchange never acknowledged it.
+                OrderState::New | OrderState::PendingNew => {
+                    warn!("Recovered order {} was never acknowledged; rejecting it", order_id);
+                    self.unacknowledged.push(order_id);
+                }
+                _ => {}
+            }
+            self.notify(OmsUpdate::OrderCreated(order.order.clone()));
+            if let Some(reason) = &order.rejection_reason {
+                self.notify(OmsUpdate::OrderRejected { order_id: order.order.order_id, reason: reason.clone() });
+            }
+        }
+        for pos in self.positions.get_all_positions() {
+            self.notify(OmsUpdate::PositionUpdate(pos));
+        }
+        if self.halted {
+            self.notify(OmsUpdate::KillSwitch(KillSwitchEvent {
+                engaged: true,
+                triggered_by: "journal recovery".to_string(),
+                reason: Some("kill switch was engaged before restart".to_string()),
+                canceled_orders: Vec::new(),
+                timestamp: chrono::Utc::now(),
+            }));
+        }
+        info!("OMS recovered {} orders and {} positions from {} journaled updates.",
+            self.orders.len(), self.positions.get_all_positions().len(), replayed);
+    }
+
+    /// Rejects orders a restart left unacknowledged, returns orders left
+    /// pending to their working state and issues the interrupted cancel or
+    /// replace again. Call once the journal is attached so the outcome is
+    /// recorded.
+    pub fn resume_interrupted(&mut self) {
+        for order_id in std::mem::take(&mut self.unacknowledged) {
+            self.reject_order(order_id, "not acknowledged before restart".to_string());
+        }
+        let pending_orders: Vec<(uuid::Uuid, OrderState)> = self
+            .orders
+            .values()
+            .filter(|o| matches!(o.current_state, OrderState::PendingCancel | OrderState::PendingReplace))
+            .map(|o| (o.order.order_id, o.fill_state()))
+            .collect();
+        for (order_id, working) in pending_orders {
+            self.set_state(order_id, working);
+        }
+        for command in std::mem::take(&mut self.interrupted) {
+            info!("Re-issuing {:?} interrupted by restart", command);
+            self.handle_command(command);
+        }
+    }
+
+    fn replay(&mut self, update: OmsUpdate) {
+        match update {
+            OmsUpdate::OrderCreated(mut order) => {
+                let state = order.state.clone();
+                order.state = OrderState::New;
+                let mut full_order = FullOrder::from(order.clone());
+                if let Err(e) = full_order.transition(state, order.placed_at) {
+                    warn!("Journal replay: {}", e);
+                }
+                self.orders.insert(order.order_id, full_order);
+            }
+            OmsUpdate::OrderStateChange { order_id, new_state, timestamp } => {
+                if let Some(order) = self.orders.get_mut(&order_id) {
+                    if let Err(e) = order.transition(new_state, timestamp) {
+                        warn!("Journal replay: {}", e);
+                    }
+                }
+            }
+            OmsUpdate::OrderReplaced { order_id, price, quantity, .. } => {
+                if let Some(order) = self.orders.get_mut(&order_id) {
+                    order.order.price = price;
+                    order.order.quantity = quantity;
+                }
+            }
+            OmsUpdate::OrderRejected { order_id, reason } => {
+                if let Some(order) = self.orders.get_mut(&order_id) {
+                    order.rejection_reason = Some(reason);
+                }
+            }
+            OmsUpdate::Fill(fill) => {
+                if let Some(order) = self.orders.get_mut(&fill.order_id) {
+                    if let Err(e) = order.apply_fill(fill.quantity, fill.price, fill.timestamp) {
+                        warn!("Journal replay: {}", e);
+                    }
+                }
+                self.positions.update_position(fill.symbol, fill.side, fill.quantity, fill.price);
+            }
+            OmsUpdate::PositionUpdate(pos) => {
+                if pos.last_price > 0.0 {
+                    self.positions.mark_to_market(&pos.symbol, pos.last_price);
+                }
+            }
+            OmsUpdate::KillSwitch(event) => self.halted = event.engaged,
+            OmsUpdate::RequestRejected { .. } => {}
+        }
+    }
+
+    pub fn handle_command(&mut self, command: OmsCommand) {
+        // Write-ahead: the command is on disk before any of its effects.
+        self.record(JournalRecord::Command(command.clone()));
+        match command {
+            OmsCommand::New(order) => self.handle_new_order(order),
+            OmsCommand::Cancel { order_id } => self.handle_cancel(order_id),
+            OmsCommand::Replace { order_id, new_price, new_quantity } => {
+                self.handle_replace(order_id, new_price, new_quantity)
+            }
+            OmsCommand::KillSwitch { triggered_by, reason } => self.engage_kill_switch(triggered_by, reason),
+            OmsCommand::Rearm { triggered_by } => self.rearm(triggered_by),
+        }
+    }
+
+    fn engage_kill_switch(&mut self, triggered_by: String, reason: String) {
+        self.halted = true;
+
+        let mut working: Vec<uuid::Uuid> = self
+            .orders
+            .values()
+            .filter(|o| o.current_state.is_working())
+            .map(|o| o.order.order_id)
+            .collect();
+        working.sort();
+
+        let mut canceled_orders = Vec::new();
+        for order_id in working {
+            self.handle_cancel(order_id);
+            if self.orders.get(&order_id).is_some_and(|o| o.current_state == OrderState::Canceled) {
+                canceled_orders.push(order_id);
+            }
+        }
+
+        self.audit(KillSwitchEvent {
+            engaged: true,
+            triggered_by,
+            reason: Some(reason),
+            canceled_orders,
+            timestamp: chrono::Utc::now(),
+        });
+    }
+
+    fn rearm(&mut self, triggered_by: String) {
+        if !self.halted {
+            info!("Re-arm requested by {} but kill switch is not engaged.", triggered_by);
+            return;
+        }
+        self.halted = false;
+        self.audit(KillSwitchEvent {
+            engaged: false,
+            triggered_by,
+            reason: None,
+            canceled_orders: Vec::new(),
+            timestamp: chrono::Utc::now(),
+        });
+    }
+
+    fn audit(&self, event: KillSwitchEvent) {
+        match serde_json::to_string(&event) {
+            Ok(json) => warn!(target: "audit", "kill switch {}", json),
+            Err(e) => error!(target: "audit", "kill switch {:?} (serialization failed: {})", event, e),
+        }
+        self.publish(OmsUpdate::KillSwitch(event));
+    }
+
+    /// Applies a validated state transition and publishes it. Illegal
+    /// transitions are logged and leave the order untouched.
+    fn set_state(&mut self, order_id: uuid::Uuid, new_state: OrderState) -> bool {
+        let Some(order) = self.orders.get_mut(&order_id) else {
+            error!("State change for unknown order {}", order_id);
+            return false;
+        };
+        if order.current_state == new_state {
+            return true;
+        }
+        let now = chrono::Utc::now();
+        if let Err(e) = order.transition(new_state.clone(), now) {
+            error!("{}", e);
+            return false;
+        }
+        self.publish(OmsUpdate::OrderStateChange {
+            order_id,
+            new_state,
+            timestamp: now,
+        });
+        true
+    }
+
+    fn reject_request(&self, order_id: uuid::Uuid, reason: String) {
+        warn!("OMS rejected request for order {}: {}", order_id, reason);
+        self.publish(OmsUpdate::RequestRejected { order_id, reason });
+    }
+
+    /// Checks that `order_id` names a working order, returning it if so.
+    fn working_order(&self, order_id: uuid::Uuid) -> Result<&FullOrder, String> {
+        let order = self.orders.get(&order_id).ok_or_else(|| "unknown order".to_string())?;
+        if !order.current_state.is_working() {
+            return Err(format!("order is {:?}", order.current_state));
+        }
+        Ok(order)
+    }
+
+    fn handle_cancel(&mut self, order_id: uuid::Uuid) {
+        info!("OMS received cancel for order {}", order_id);
+        if let Err(reason) = self.working_order(order_id) {
+            self.reject_request(order_id, reason);
+            return;
+        }
+
+        let previous = self.orders[&order_id].current_state.clone();
+        if !self.set_state(order_id, OrderState::PendingCancel) {
+            self.reject_request(order_id, format!("cannot cancel from {:?}", previous));
+            return;
+        }
+        match self.matching.cancel(order_id) {
+            Some(_) => {
+                self.set_state(order_id, OrderState::Canceled);
+            }
+            None => {
+                self.set_state(order_id, previous);
+                self.reject_request(order_id, "order is no longer resting".to_string());
+            }
+        }
+    }
+
+    fn handle_replace(&mut self, order_id: uuid::Uuid, new_price: Option<f64>, new_quantity: Option<f64>) {
+        info!("OMS received replace for order {}: price {:?}, quantity {:?}", order_id, new_price, new_quantity);
+        let order = match self.working_order(order_id) {
+            Ok(order) => order,
+            Err(reason) => {
+                self.reject_request(order_id, reason);
+                return;
+            }
+        };
+
+        let invalid = if new_price.is_none() && new_quantity.is_none() {
+            Some("nothing to amend".to_string())
+        } else if new_price.is_some() && order.order.order_type != OrderType::Limit {
+            Some("only limit orders have a price to amend".to_string())
+        } else if new_price.is_some_and(|p| p <= 0.0) {
+            Some("price must be positive".to_string())
+        } else if new_quantity.is_some_and(|q| q <= order.filled_quantity) {
+            Some(format!("quantity must exceed filled quantity {}", order.filled_quantity))
+        } else {
+            None
+        };
+        if let Some(reason) = invalid {
+            self.reject_request(order_id, reason);
+            return;
+        }
+
+        let previous = order.current_state.clone();
+        if !self.set_state(order_id, OrderState::PendingReplace) {
+            self.reject_request(order_id, format!("cannot amend from {:?}", previous));
+            return;
+        }
+        let Some(events) = self.matching.replace(order_id, new_price, new_quantity, chrono::Utc::now()) else {
+            self.set_state(order_id, previous);
+            self.reject_request(order_id, "order is no longer resting".to_string());
+            return;
+        };
+
+        if let Some(order) = self.orders.get_mut(&order_id) {
+            if new_price.is_some() {
+                order.order.price = new_price;
+            }
+            if let Some(quantity) = new_quantity {
+                order.order.quantity = quantity;
+            }
+            let (price, quantity) = (order.order.price, order.order.quantity);
+            self.publish(OmsUpdate::OrderReplaced {
+                order_id,
+                price,
+                quantity,
+                timestamp: chrono::Utc::now(),
+            });
+        }
+        self.apply_match_events(events);
+    }
+
+    fn handle_new_order(&mut self, mut order: Order) {
+        info!("OMS received order: {:?}", order);
+        order.state = OrderState::New;
+        let mut full_order = FullOrder::from(order.clone());
+        if let Err(e) = full_order.transition(OrderState::PendingNew, chrono::Utc::now()) {
+            error!("{}", e);
+            return;
+        }
+        order.state = OrderState::PendingNew;
+        self.orders.insert(order.order_id, full_order);
+        self.publish(OmsUpdate::OrderCreated(order.clone()));
+
+        if self.halted {
+            self.reject_order(order.order_id, "kill switch engaged".to_string());
+            return;
+        }
+
+        let now = chrono::Utc::now();
+        self.roll_day(now);
+        let context = self.risk_context(&order, now);
+        if let Err(violation) = self.risk.check(&order, &context) {
+            self.reject_order(order.order_id, violation.to_string());
+            return;
+        }
+
+        let events = self.matching.submit(order, now);
+        self.apply_match_events(events);
+    }
+
+    /// Starts a new daily loss baseline at midnight and journals it, so a
+    /// restart doesn't forget the day's losses.
+    fn roll_day(&mut self, now: chrono::DateTime<chrono::Utc>) {
+        if let Some(start) = self.risk.roll_day(self.positions.total_pnl(), now) {
+            info!("Daily loss baseline for {} set at PnL {:.2}", start.day, start.pnl);
+            self.record(JournalRecord::DayStart(start));
+        }
+    }
+
+    fn risk_context(&self, order: &Order, now: chrono::DateTime<chrono::Utc>) -> RiskContext {
+        let working: Vec<&FullOrder> = self
+            .orders
+            .values()
+            .filter(|o| o.order.order_id != order.order_id)
+            .filter(|o| o.current_state.is_working() || o.current_state == OrderState::PendingNew)
+            .collect();
+        RiskContext {
+            last_price: self.matching.last_price(&order.symbol),
+            position: self.positions.get_position(&order.symbol).map_or(0.0, |p| p.quantity),
+            working_same_side: working
+                .iter()
+                .filter(|o| o.order.symbol == order.symbol && o.order.side == order.side)
+                .map(|o| o.remaining_quantity())
+                .sum(),
+            open_orders: working.len(),
+            total_pnl: self.positions.total_pnl(),
+            now,
+        }
+    }
+
+    fn reject_order(&mut self, order_id: uuid::Uuid, reason: String) {
+        warn!("OMS rejected order {}: {}", order_id, reason);
+        if let Some(order) = self.orders.get_mut(&order_id) {
+            order.rejection_reason = Some(reason.clone());
+        }
+        if self.set_state(order_id, OrderState::Rejected) {
+            self.publish(OmsUpdate::OrderRejected { order_id, reason });
+        }
+    }
+
+    pub fn handle_market_data(&mut self, update: &MarketDataUpdate) {
+        let events = self.matching.on_market_data(update);
+        self.apply_match_events(events);
+
+        if let Some(last_price) = update.last_price {
+            if self.positions.mark_to_market(&update.symbol, last_price) {
+                self.marked_symbols.insert(update.symbol.clone());
+            }
+        }
+    }
+
+    pub fn publish_marks(&mut self) {
+        // Keeps the daily loss baseline rolling over at midnight even without order flow.
+        self.roll_day(chrono::Utc::now());
+        // Marks are rebuilt from fills and live prices, so they aren't journaled.
+        let symbols: Vec<String> = self.marked_symbols.drain().collect();
+        for symbol in symbols {
+            if let Some(pos) = self.positions.get_position(&symbol) {
+                self.notify(OmsUpdate::PositionUpdate(pos));
+            }
+        }
+    }
+
+    fn apply_match_events(&mut self, events: Vec<MatchEvent>) {
+        for event in events {
+            match event {
+                MatchEvent::Fill(fill) => self.apply_fill(fill),
+                // Fill-driven states are derived from the cumulative fill
+                // quantity. A fill has usually set them already; an amend that
+                // didn't fill still has to leave `PendingReplace`.
+                MatchEvent::StateChange(order_id, state @ (OrderState::PartialFill | OrderState::Filled)) => {
+                    let Some(order) = self.orders.get(&order_id) else {
+                        warn!("State change for unknown order {}", order_id);
+                        continue;
+                    };
+                    if order.current_state == state {
+                        debug!("Exchange reported fill state for {}; derived from fills", order_id);
+                        continue;
+                    }
+                    let derived = order.fill_state();
+                    self.set_state(order_id, derived);
+                }
+                MatchEvent::StateChange(order_id, OrderState::Rejected) => {
+                    self.reject_order(order_id, "rejected by exchange".to_string());
+                }
+                MatchEvent::StateChange(order_id, new_state) => {
+                    self.set_state(order_id, new_state);
+                }
+            }
+        }
+    }
+
+    fn apply_fill(&mut self, fill: Fill) {
+        let new_state = match self.orders.get_mut(&fill.order_id) {
+            Some(order) => match order.apply_fill(fill.quantity, fill.price, fill.timestamp) {
+                Ok(new_state) => new_state,
+                Err(e) => {
+                    error!("Dropping fill: {}", e);
+                    return;
+                }
+            },
+            None => {
+                warn!("Fill for unknown order {}", fill.order_id);
+                None
+            }
+        };
+        info!("Fill for order {}: {:?} {} {} @ {}", fill.order_id, fill.side, fill.quantity, fill.symbol, fill.price);
+
+        self.positions.update_position(fill.symbol.clone(), fill.side.clone(), fill.quantity, fill.price);
+        let position = self.positions.get_position(&fill.symbol);
+        let (order_id, timestamp) = (fill.order_id, fill.timestamp);
+        self.publish(OmsUpdate::Fill(fill));
+        if let Some(new_state) = new_state {
+            self.publish(OmsUpdate::OrderStateChange { order_id, new_state, timestamp });
+        }
+        if let Some(pos) = position {
+            self.publish(OmsUpdate::PositionUpdate(pos));
+        }
+    }
+
+    pub fn log_positions(&self) {
+        for pos in self.positions.get_all_positions() {
+            info!("Final position {}: qty {} @ {:.2}, realized {:.2}, unrealized {:.2}",
+                pos.symbol, pos.quantity, pos.avg_cost, pos.pnl_realized, pos.pnl_unrealized);
+        }
+    }
 }
 
 pub async fn run_oms(
-    mut ui_order_rx: mpsc::UnboundedReceiver<Order>,
+    config: OmsConfig,
+    mut ui_order_rx: mpsc::UnboundedReceiver<OmsCommand>,
+    mut md_rx: mpsc::UnboundedReceiver<MarketDataUpdate>,
     oms_ui_tx: mpsc::UnboundedSender<OmsUpdate>,
 ) -> Result<()> {
     info!("Order Management System (OMS) started.");
 
-    let orders: Arc<RwLock<HashMap<uuid::Uuid, FullOrder>>> = Arc::new(RwLock::new(HashMap::new()));
-    let position_manager = Arc::new(RwLock::new(PositionManager::new()));
+    let journal_path = config.journal_enabled.then(|| config.journal_path.clone());
+    let mut oms = Oms::new(config, oms_ui_tx);
+    if let Some(path) = journal_path {
+        let (journal, entries) = Journal::open(std::path::Path::new(&path))?;
+        oms.recover(entries);
+        oms.attach_journal(journal);
+        oms.resume_interrupted();
+    }
+    let mut mark_publish = tokio::time::interval(tokio::time::Duration::from_secs(1));
 
     loop {
         tokio::select! {
-            Some(mut order) = ui_order_rx.recv() => {
-                info!("OMS received order: {:?}", order);
-                let order_id = order.order_id;
-                order.state = OrderState::PendingNew; 
-                let oms_order = FullOrder::from(order.clone()); 
-                orders.write().insert(order_id, oms_order);
-
-                if let Err(e) = oms_ui_tx.send(OmsUpdate::OrderCreated(order.clone())) {
-                    error!("Failed to send OrderCreated update to UI: {}", e);
-                }
-
-                let oms_ui_tx_clone = oms_ui_tx.clone();
-                let orders_clone = orders.clone();
-                let position_manager_clone = position_manager.clone();
-
-                tokio::spawn(async move {
-                    tokio::time::sleep(tokio::time::Duration::from_millis(100 + rand::random::<u64>() % 200)).await;
-
-                    let mut orders_guard = orders_clone.write();
-                    if let Some(order_to_execute) = orders_guard.get_mut(&order_id) {
-                        order_to_execute.current_state = OrderState::Filled; 
-                        order_to_execute.filled_quantity = order_to_execute.order.quantity;
-                        order_to_execute.avg_fill_price = order_to_execute.order.price.unwrap_or(0.0); 
-
-                        info!("Simulating fill for order {}: {:?}", order_id, order_to_execute.current_state); 
-
-                        position_manager_clone.write().update_position(
-                            order_to_execute.order.symbol.clone(),
-                            order_to_execute.order.side.clone(),
-                            order_to_execute.filled_quantity,
-                            order_to_execute.avg_fill_price,
-                        );
-
-                        if let Err(e) = oms_ui_tx_clone.send(OmsUpdate::OrderStateChange {
-                            order_id,
-                            new_state: OrderState::Filled,
-                            timestamp: chrono::Utc::now(),
-                        }) {
-                            error!("Failed to send OrderStateChange update to UI: {}", e);
-                        }
-
-                        let current_pos = position_manager_clone.read().get_position(&order_to_execute.order.symbol);
-                        if let Some(pos) = current_pos {
-                             if let Err(e) = oms_ui_tx_clone.send(OmsUpdate::PositionUpdate(pos)) {
-                                error!("Failed to send PositionUpdate to UI: {}", e);
-                            }
-                        }
-
-                    } else {
-                        error!("Attempted to simulate fill for non-existent order: {}", order_id);
-                    }
-                });
+            Some(command) = ui_order_rx.recv() => {
+                oms.handle_command(command);
+            }
+            Some(update) = md_rx.recv() => {
+                oms.handle_market_data(&update);
+            }
+            _ = mark_publish.tick() => {
+                oms.publish_marks();
             }
             _ = tokio::signal::ctrl_c() => {
                 info!("OMS received Ctrl-C, shutting down.");
+                oms.log_positions();
                 break;
             }
         }
     }
     Ok(())
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data_model::TimeInForce;
+    use crate::oms::journal::JournalEntry;
+
+    fn entry(record: JournalRecord) -> JournalEntry {
+        JournalEntry { timestamp: chrono::Utc::now(), record }
+    }
+
+    fn resting_order(state: OrderState) -> (Order, Vec<JournalEntry>) {
+        let mut order = Order::new("BTCUSDT".to_string(), Side::Buy, OrderType::Limit, 1.0, Some(100.0), TimeInForce::GTC);
+        order.state = OrderState::PendingNew;
+        let order_id = order.order_id;
+        let change = |new_state| {
+            entry(JournalRecord::Update(OmsUpdate::OrderStateChange { order_id, new_state, timestamp: chrono::Utc::now() }))
+        };
+        let mut entries = vec![entry(JournalRecord::Update(OmsUpdate::OrderCreated(order.clone()))), change(OrderState::Open)];
+        if state == OrderState::PendingReplace {
+            entries.push(entry(JournalRecord::Command(OmsCommand::Replace { order_id, new_price: Some(99.0), new_quantity: None })));
+        }
+        entries.push(change(state));
+        (order, entries)
+    }
+
+    fn recovered(entries: Vec<JournalEntry>) -> Oms {
+        let (updates_tx, _updates_rx) = mpsc::unbounded_channel();
+        let mut oms = Oms::new(OmsConfig { journal_enabled: false, ..Default::default() }, updates_tx);
+        oms.recover(entries);
+        oms.resume_interrupted();
+        oms
+    }
+
+    fn quote(bid: f64, ask: f64, ask_quantity: f64) -> MarketDataUpdate {
+        MarketDataUpdate {
+            symbol: "BTCUSDT".to_string(),
+            timestamp: chrono::Utc::now(),
+            bid_price: Some(bid),
+            bid_quantity: Some(1.0),
+            ask_price: Some(ask),
+            ask_quantity: Some(ask_quantity),
+            last_price: None,
+            last_quantity: None,
+            aggressor: None,
+        }
+    }
+
+    #[test]
+    fn amending_a_partially_filled_order_leaves_it_cancelable() {
+        let (updates_tx, _updates_rx) = mpsc::unbounded_channel();
+        let mut oms = Oms::new(OmsConfig { journal_enabled: false, ..Default::default() }, updates_tx);
+        oms.handle_market_data(&quote(99.0, 101.0, 5.0));
+        let order = Order::new("BTCUSDT".to_string(), Side::Buy, OrderType::Limit, 3.0, Some(100.0), TimeInForce::GTC);
+        let order_id = order.order_id;
+        oms.handle_command(OmsCommand::New(order));
+        oms.handle_market_data(&quote(99.0, 100.0, 1.0));
+        assert_eq!(oms.orders[&order_id].current_state, OrderState::PartialFill);
+
+        oms.handle_command(OmsCommand::Replace { order_id, new_price: Some(99.5), new_quantity: None });
+        assert_eq!(oms.orders[&order_id].current_state, OrderState::PartialFill);
+        assert_eq!(oms.orders[&order_id].order.price, Some(99.5));
+
+        oms.handle_command(OmsCommand::Cancel { order_id });
+        assert_eq!(oms.orders[&order_id].current_state, OrderState::Canceled);
+    }
+
+    fn limit(side: Side, price: f64) -> Order {
+        Order::new("BTCUSDT".to_string(), side, OrderType::Limit, 1.0, Some(price), TimeInForce::GTC)
+    }
+
+    #[test]
+    fn kill_switch_cancels_working_orders_and_blocks_new_ones_until_rearmed() {
+        let (updates_tx, _updates_rx) = mpsc::unbounded_channel();
+        let mut oms = Oms::new(OmsConfig { journal_enabled: false, ..Default::default() }, updates_tx);
+        oms.handle_market_data(&quote(99.0, 101.0, 5.0));
+        let (bid, ask) = (limit(Side::Buy, 99.5), limit(Side::Sell, 100.5));
+        let (bid_id, ask_id) = (bid.order_id, ask.order_id);
+        oms.handle_command(OmsCommand::New(bid));
+        oms.handle_command(OmsCommand::New(ask));
+
+        oms.handle_command(OmsCommand::KillSwitch { triggered_by: "test".to_string(), reason: "drill".to_string() });
+        assert!(oms.halted);
+        assert_eq!(oms.orders[&bid_id].current_state, OrderState::Canceled);
+        assert_eq!(oms.orders[&ask_id].current_state, OrderState::Canceled);
+
+        let blocked = limit(Side::Buy, 99.5);
+        let blocked_id = blocked.order_id;
+        oms.handle_command(OmsCommand::New(blocked));
+        assert_eq!(oms.orders[&blocked_id].current_state, OrderState::Rejected);
+        assert_eq!(oms.orders[&blocked_id].rejection_reason.as_deref(), Some("kill switch engaged"));
+
+        oms.handle_command(OmsCommand::Rearm { triggered_by: "test".to_string() });
+        assert!(!oms.halted);
+        let resumed = limit(Side::Buy, 99.5);
+        let resumed_id = resumed.order_id;
+        oms.handle_command(OmsCommand::New(resumed));
+        assert_eq!(oms.orders[&resumed_id].current_state, OrderState::Open);
+    }
+
+    #[test]
+    fn recovery_rejects_an_unacknowledged_order() {
+        let (order, mut entries) = resting_order(OrderState::Open);
+        entries.truncate(1);
+        let path = std::env::temp_dir().join(format!("oms_journal_{}.jsonl", uuid::Uuid::new_v4()));
+        let (updates_tx, _updates_rx) = mpsc::unbounded_channel();
+        let mut oms = Oms::new(OmsConfig { journal_enabled: false, ..Default::default() }, updates_tx);
+        oms.recover(entries);
+        oms.attach_journal(Journal::open(&path).unwrap().0);
+        oms.resume_interrupted();
+
+        let recovered = &oms.orders[&order.order_id];
+        assert_eq!(recovered.current_state, OrderState::Rejected);
+        assert_eq!(recovered.rejection_reason.as_deref(), Some("not acknowledged before restart"));
+        let (_, journaled) = Journal::open(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert!(journaled.iter().any(|e| matches!(
+            &e.record,
+            JournalRecord::Update(OmsUpdate::OrderStateChange { new_state: OrderState::Rejected, .. })
+        )));
+
+        let probe = Order::new("BTCUSDT".to_string(), Side::Buy, OrderType::Limit, 1.0, Some(100.0), TimeInForce::GTC);
+        assert_eq!(oms.risk_context(&probe, chrono::Utc::now()).open_orders, 0);
+    }
+
+    #[test]
+    fn recovery_completes_an_interrupted_cancel() {
+        let (order, entries) = resting_order(OrderState::PendingCancel);
+        let oms = recovered(entries);
+        assert_eq!(oms.orders[&order.order_id].current_state, OrderState::Canceled);
+    }
+
+    #[test]
+    fn recovery_reissues_an_interrupted_replace() {
+        let (order, entries) = resting_order(OrderState::PendingReplace);
+        let oms = recovered(entries);
+        let recovered = &oms.orders[&order.order_id];
+        assert_eq!(recovered.current_state, OrderState::Open);
+        assert_eq!(recovered.order.price, Some(99.0));
+    }
+}
@@ -1,9 +1,9 @@
 use serde::{Deserialize, Serialize};
 use chrono::{DateTime, Utc};
-use uuid::Uuid; 
-
-use crate::data_model::Order as UiOrder;
+use thiserror::Error;
+use uuid::Uuid;
 
+use crate::data_model::{Order as UiOrder, QTY_EPSILON};
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum OrderState {
@@ -14,10 +14,60 @@ pub enum OrderState {
     PartialFill,
     Canceled,
     PendingCancel,
+    PendingReplace,
     Rejected,
     Expired,
 }
 
+impl OrderState {
+    /// Resting at the exchange and eligible for cancel or amend.
+    pub fn is_working(&self) -> bool {
+        matches!(self, OrderState::Open | OrderState::PartialFill)
+    }
+
+    pub fn is_terminal(&self) -> bool {
+        matches!(self, OrderState::Filled | OrderState::Canceled | OrderState::Rejected | OrderState::Expired)
+    }
+
+    /// Whether the order lifecycle allows moving from `self` to `next`.
+    /// Fills may still arrive while a cancel or replace is pending.
+    pub fn can_transition_to(&self, next: &OrderState) -> bool {
+        use OrderState::*;
+        match self {
+            New => matches!(next, PendingNew | Rejected),
+            PendingNew => matches!(next, Open | PartialFill | Filled | Canceled | Rejected | Expired),
+            Open => matches!(next, PartialFill | Filled | PendingCancel | PendingReplace | Canceled | Expired),
+            PartialFill => matches!(next, Filled | PendingCancel | PendingReplace | Canceled | Expired),
+            PendingCancel => matches!(next, Canceled | Open | PartialFill | Filled),
+            PendingReplace => matches!(next, Open | PartialFill | Filled | Canceled),
+            Filled | Canceled | Rejected | Expired => false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Error)]
+pub enum OrderStateError {
+    #[error("order {order_id}: illegal transition {from:?} -> {to:?}")]
+    IllegalTransition {
+        order_id: Uuid,
+        from: OrderState,
+        to: OrderState,
+    },
+    #[error("order {order_id}: fill of {fill_quantity} exceeds remaining {remaining}")]
+    Overfill {
+        order_id: Uuid,
+        fill_quantity: f64,
+        remaining: f64,
+    },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StateTransition {
+    pub from: OrderState,
+    pub to: OrderState,
+    pub timestamp: DateTime<Utc>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FullOrder {
     pub order: UiOrder,
@@ -29,6 +79,7 @@ pub struct FullOrder {
     pub last_fill_time: Option<DateTime<Utc>>,
     pub exchange_order_id: Option<String>,
     pub rejection_reason: Option<String>,
+    pub history: Vec<StateTransition>,
 }
 
 impl FullOrder {
@@ -43,10 +94,146 @@ impl FullOrder {
             last_fill_time: None,
             exchange_order_id: None,
             rejection_reason: None,
+            history: Vec::new(),
+        }
+    }
+
+    pub fn remaining_quantity(&self) -> f64 {
+        (self.order.quantity - self.filled_quantity).max(0.0)
+    }
+
+    /// The working or filled state implied by the quantity filled so far.
+    pub fn fill_state(&self) -> OrderState {
+        if self.remaining_quantity() <= QTY_EPSILON {
+            OrderState::Filled
+        } else if self.filled_quantity > 0.0 {
+            OrderState::PartialFill
+        } else {
+            OrderState::Open
+        }
+    }
+
+    /// Moves the order to `new_state`, recording the transition. Moving to
+    /// the current state is a no-op.
+    pub fn transition(&mut self, new_state: OrderState, at: DateTime<Utc>) -> Result<(), OrderStateError> {
+        if self.current_state == new_state {
+            return Ok(());
+        }
+        if !self.current_state.can_transition_to(&new_state) {
+            return Err(OrderStateError::IllegalTransition {
+                order_id: self.order.order_id,
+                from: self.current_state.clone(),
+                to: new_state,
+            });
         }
+        self.history.push(StateTransition {
+            from: self.current_state.clone(),
+            to: new_state.clone(),
+            timestamp: at,
+        });
+        self.current_state = new_state.clone();
+        self.order.state = new_state;
+        Ok(())
     }
 
-    pub fn update_state(&mut self, new_state: OrderState) {
-        self.current_state = new_state;
+    /// Books a fill and derives `PartialFill`/`Filled` from the cumulative
+    /// filled quantity. Returns the new state if it changed.
+    pub fn apply_fill(&mut self, quantity: f64, price: f64, time: DateTime<Utc>) -> Result<Option<OrderState>, OrderStateError> {
+        let remaining = self.remaining_quantity();
+        if quantity > remaining + QTY_EPSILON {
+            return Err(OrderStateError::Overfill {
+                order_id: self.order.order_id,
+                fill_quantity: quantity,
+                remaining,
+            });
+        }
+
+        let next = if remaining - quantity <= QTY_EPSILON {
+            OrderState::Filled
+        } else {
+            OrderState::PartialFill
+        };
+        let changed = self.current_state != next;
+        self.transition(next.clone(), time)?;
+
+        let total = self.filled_quantity + quantity;
+        if total > 0.0 {
+            self.avg_fill_price = (self.avg_fill_price * self.filled_quantity + price * quantity) / total;
+        }
+        self.filled_quantity = total;
+        self.last_fill_price = Some(price);
+        self.last_fill_quantity = Some(quantity);
+        self.last_fill_time = Some(time);
+
+        Ok(changed.then_some(next))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data_model::{OrderType, Side, TimeInForce};
+
+    fn working_order(quantity: f64) -> FullOrder {
+        let mut order = FullOrder::from(UiOrder::new(
+            "BTCUSDT".to_string(),
+            Side::Buy,
+            OrderType::Limit,
+            quantity,
+            Some(100.0),
+            TimeInForce::GTC,
+        ));
+        order.transition(OrderState::PendingNew, Utc::now()).unwrap();
+        order.transition(OrderState::Open, Utc::now()).unwrap();
+        order
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn transition_table() {
+        use OrderState::*;
+        assert!(New.can_transition_to(&PendingNew));
+        assert!(!New.can_transition_to(&Open));
+        assert!(PendingNew.can_transition_to(&Filled));
+        assert!(Open.can_transition_to(&PendingReplace));
+        assert!(PartialFill.can_transition_to(&Filled));
+        assert!(!PartialFill.can_transition_to(&Open));
+        assert!(PendingCancel.can_transition_to(&Filled));
+        assert!(!PendingReplace.can_transition_to(&Rejected));
+        for terminal in [Filled, Canceled, Rejected, Expired] {
+            assert!(terminal.is_terminal());
+            assert!(!terminal.can_transition_to(&Open));
+        }
+    }
+
+    #[test]
+    fn illegal_transition_is_rejected_and_not_recorded() {
+        let mut order = working_order(1.0);
+        order.transition(OrderState::Canceled, Utc::now()).unwrap();
+        let err = order.transition(OrderState::Open, Utc::now()).unwrap_err();
+        assert!(matches!(err, OrderStateError::IllegalTransition { from: OrderState::Canceled, to: OrderState::Open, .. }));
+        assert_eq!(order.current_state, OrderState::Canceled);
+        assert_eq!(order.history.len(), 3);
+    }
+
+    #[test]
+    fn fills_derive_partial_then_filled() {
+        let mut order = working_order(3.0);
+        assert_eq!(order.apply_fill(1.0, 100.0, Utc::now()).unwrap(), Some(OrderState::PartialFill));
+        assert_eq!(order.apply_fill(1.0, 103.0, Utc::now()).unwrap(), None);
+        assert_eq!(order.current_state, OrderState::PartialFill);
+        assert_eq!(order.apply_fill(1.0, 103.0, Utc::now()).unwrap(), Some(OrderState::Filled));
+        assert_eq!(order.order.state, OrderState::Filled);
+        assert!((order.avg_fill_price - 102.0).abs() < 1e-9);
+        assert_eq!(order.remaining_quantity(), 0.0);
+    }
+
+    #[test]
+    fn overfill_is_rejected_without_booking() {
+        let mut order = working_order(1.0);
+        order.apply_fill(0.6, 100.0, Utc::now()).unwrap();
+        let err = order.apply_fill(0.5, 100.0, Utc::now()).unwrap_err();
+        assert!(matches!(err, OrderStateError::Overfill { .. }));
+        assert!((order.filled_quantity - 0.6).abs() < 1e-12);
+        assert_eq!(order.current_state, OrderState::PartialFill);
+    }
+}
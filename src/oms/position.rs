@@ -1,6 +1,6 @@
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
-use crate::data_model::Side;
+use crate::data_model::{Side, QTY_EPSILON};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Position {
@@ -11,16 +11,163 @@ pub struct Position {
     pub pnl_unrealized: f64,
     pub pnl_realized: f64,
 }
+
 impl Position {
     pub fn new(symbol: String) -> Self {
         Self { symbol, quantity: 0.0, avg_cost: 0.0, last_price: 0.0, pnl_unrealized: 0.0, pnl_realized: 0.0 }
     }
-    pub fn update_unrealized_pnl(&mut self) { /* ... */ }
+
+    pub fn is_flat(&self) -> bool {
+        self.quantity.abs() < QTY_EPSILON
+    }
+
+    pub fn update_unrealized_pnl(&mut self) {
+        self.pnl_unrealized = if self.is_flat() || self.last_price <= 0.0 {
+            0.0
+        } else {
+            (self.last_price - self.avg_cost) * self.quantity
+        };
+    }
+
+    pub fn mark(&mut self, price: f64) {
+        self.last_price = price;
+        self.update_unrealized_pnl();
+    }
+
+    /// Applies a fill to the position. Fills on the same side as the current
+    /// position extend it at a weighted average cost; opposite fills realize
+    /// PnL on the closed quantity and, if they cross zero, open the remainder
+    /// at the fill price.
+    pub fn apply_fill(&mut self, side: &Side, quantity: f64, fill_price: f64) {
+        if quantity <= 0.0 {
+            return;
+        }
+        let signed_qty = match side {
+            Side::Buy => quantity,
+            Side::Sell => -quantity,
+        };
+
+        if self.is_flat() || self.quantity.signum() == signed_qty.signum() {
+            let held = self.quantity.abs();
+            self.avg_cost = (held * self.avg_cost + quantity * fill_price) / (held + quantity);
+            self.quantity += signed_qty;
+        } else {
+            let held = self.quantity.abs();
+            let closed = quantity.min(held);
+            self.pnl_realized += closed * (fill_price - self.avg_cost) * self.quantity.signum();
+            self.quantity += signed_qty;
+
+            if self.is_flat() {
+                self.quantity = 0.0;
+                self.avg_cost = 0.0;
+            } else if quantity > held {
+                self.avg_cost = fill_price;
+            }
+        }
+
+        if self.last_price <= 0.0 {
+            self.last_price = fill_price;
+        }
+        self.update_unrealized_pnl();
+    }
+}
+
+#[derive(Default)]
+pub struct PositionManager {
+    positions: HashMap<String, Position>,
 }
-pub struct PositionManager { positions: HashMap<String, Position>, }
+
 impl PositionManager {
-    pub fn new() -> Self { Self { positions: HashMap::new(), } }
-    pub fn update_position(&mut self, _symbol: String, _side: Side, _quantity: f64, _fill_price: f64) { /* ... */ }
-    pub fn get_position(&self, _symbol: &str) -> Option<Position> { None }
-    pub fn get_all_positions(&self) -> Vec<Position> { vec![] }
-}
\ No newline at end of file
+    pub fn new() -> Self {
+        Self { positions: HashMap::new() }
+    }
+
+    pub fn update_position(&mut self, symbol: String, side: Side, quantity: f64, fill_price: f64) {
+        self.positions
+            .entry(symbol.clone())
+            .or_insert_with(|| Position::new(symbol))
+            .apply_fill(&side, quantity, fill_price);
+    }
+
+    /// Marks the symbol's position to `last_price`. Returns whether a position
+    /// exists for the symbol, i.e. whether its PnL may have changed.
+    pub fn mark_to_market(&mut self, symbol: &str, last_price: f64) -> bool {
+        match self.positions.get_mut(symbol) {
+            Some(pos) => {
+                pos.mark(last_price);
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn get_position(&self, symbol: &str) -> Option<Position> {
+        self.positions.get(symbol).cloned()
+    }
+
+    pub fn get_all_positions(&self) -> Vec<Position> {
+        self.positions.values().cloned().collect()
+    }
+
+    pub fn total_pnl(&self) -> f64 {
+        self.positions.values().map(|p| p.pnl_realized + p.pnl_unrealized).sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!((actual - expected).abs() < 1e-9, "expected {}, got {}", expected, actual);
+    }
+
+    #[test]
+    fn same_side_fills_average_the_cost() {
+        let mut pos = Position::new("BTCUSDT".to_string());
+        pos.apply_fill(&Side::Buy, 1.0, 100.0);
+        pos.apply_fill(&Side::Buy, 3.0, 104.0);
+        assert_close(pos.quantity, 4.0);
+        assert_close(pos.avg_cost, 103.0);
+        assert_close(pos.pnl_realized, 0.0);
+    }
+
+    #[test]
+    fn reducing_realizes_pnl_and_keeps_the_cost() {
+        let mut pos = Position::new("BTCUSDT".to_string());
+        pos.apply_fill(&Side::Sell, 2.0, 100.0);
+        pos.apply_fill(&Side::Buy, 0.5, 90.0);
+        assert_close(pos.quantity, -1.5);
+        assert_close(pos.avg_cost, 100.0);
+        assert_close(pos.pnl_realized, 5.0);
+
+        pos.apply_fill(&Side::Buy, 1.5, 110.0);
+        assert!(pos.is_flat());
+        assert_close(pos.avg_cost, 0.0);
+        assert_close(pos.pnl_realized, -10.0);
+        assert_close(pos.pnl_unrealized, 0.0);
+    }
+
+    #[test]
+    fn flipping_opens_the_remainder_at_the_fill_price() {
+        let mut pos = Position::new("BTCUSDT".to_string());
+        pos.apply_fill(&Side::Buy, 1.0, 100.0);
+        pos.apply_fill(&Side::Sell, 3.0, 120.0);
+        assert_close(pos.quantity, -2.0);
+        assert_close(pos.avg_cost, 120.0);
+        assert_close(pos.pnl_realized, 20.0);
+
+        pos.mark(110.0);
+        assert_close(pos.pnl_unrealized, 20.0);
+    }
+
+    #[test]
+    fn manager_totals_realized_and_unrealized() {
+        let mut positions = PositionManager::new();
+        positions.update_position("BTCUSDT".to_string(), Side::Buy, 1.0, 100.0);
+        positions.update_position("BTCUSDT".to_string(), Side::Sell, 0.5, 110.0);
+        assert!(positions.mark_to_market("BTCUSDT", 120.0));
+        assert!(!positions.mark_to_market("ETHUSDT", 10.0));
+        assert_close(positions.total_pnl(), 5.0 + 10.0);
+    }
+}
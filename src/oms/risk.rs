@@ -0,0 +1,245 @@
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::config::RiskConfig;
+use crate::data_model::{Order, Side};
+
+#[derive(Debug, Clone, PartialEq, Error)]
+pub enum RiskViolation {
+    #[error("quantity {quantity} exceeds max order quantity {limit}")]
+    OrderQuantity { quantity: f64, limit: f64 },
+    #[error("notional {notional:.2} exceeds max order notional {limit:.2}")]
+    OrderNotional { notional: f64, limit: f64 },
+    #[error("price {price} is {deviation_pct:.2}% from last {last}, collar is {collar_pct:.2}%")]
+    PriceCollar { price: f64, last: f64, deviation_pct: f64, collar_pct: f64 },
+    #[error("projected position {projected} exceeds max position {limit}")]
+    Position { projected: f64, limit: f64 },
+    #[error("{open} open orders, max is {limit}")]
+    OpenOrders { open: usize, limit: usize },
+    #[error("daily loss {loss:.2} breached limit {limit:.2}; only risk-reducing orders allowed")]
+    DailyLoss { loss: f64, limit: f64 },
+}
+
+/// Account state an order is checked against.
+#[derive(Debug, Clone)]
+pub struct RiskContext {
+    pub last_price: Option<f64>,
+    pub position: f64,
+    /// Remaining quantity of working orders on the same symbol and side.
+    pub working_same_side: f64,
+    pub open_orders: usize,
+    pub total_pnl: f64,
+    pub now: DateTime<Utc>,
+}
+
+/// Total PnL when the UTC day began; the daily loss is measured from it.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct DayStart {
+    pub day: NaiveDate,
+    pub pnl: f64,
+}
+
+pub struct RiskManager {
+    config: RiskConfig,
+    day_start: Option<DayStart>,
+}
+
+impl RiskManager {
+    pub fn new(config: RiskConfig) -> Self {
+        Self { config, day_start: None }
+    }
+
+    /// Starts a new day at `total_pnl` if `now` is past the current one.
+    /// Returns the new baseline so it can be journaled.
+    pub fn roll_day(&mut self, total_pnl: f64, now: DateTime<Utc>) -> Option<DayStart> {
+        let today = now.date_naive();
+        if self.day_start.is_some_and(|start| start.day >= today) {
+            return None;
+        }
+        let start = DayStart { day: today, pnl: total_pnl };
+        self.day_start = Some(start);
+        Some(start)
+    }
+
+    /// Restores a journaled baseline after a restart.
+    pub fn restore_day(&mut self, start: DayStart) {
+        self.day_start = Some(start);
+    }
+
+    /// Loss since the start of the day `now` falls in, as a positive number.
+    /// Zero until `roll_day` has started that day.
+    pub fn daily_loss(&self, total_pnl: f64, now: DateTime<Utc>) -> f64 {
+        match self.day_start {
+            Some(start) if start.day == now.date_naive() => (start.pnl - total_pnl).max(0.0),
+            _ => 0.0,
+        }
+    }
+
+    pub fn check(&self, order: &Order, ctx: &RiskContext) -> Result<(), RiskViolation> {
+        let cfg = &self.config;
+
+        if let Some(limit) = cfg.max_order_quantity {
+            if order.quantity > limit {
+                return Err(RiskViolation::OrderQuantity { quantity: order.quantity, limit });
+            }
+        }
+
+        let reference_price = order.price.or(ctx.last_price);
+        if let (Some(limit), Some(price)) = (cfg.max_order_notional, reference_price) {
+            let notional = order.quantity * price;
+            if notional > limit {
+                return Err(RiskViolation::OrderNotional { notional, limit });
+            }
+        }
+
+        if let (Some(collar), Some(price), Some(last)) = (cfg.price_collar_pct, order.price, ctx.last_price) {
+            let deviation = if last > 0.0 { (price - last).abs() / last } else { 0.0 };
+            if deviation > collar {
+                return Err(RiskViolation::PriceCollar {
+                    price,
+                    last,
+                    deviation_pct: deviation * 100.0,
+                    collar_pct: collar * 100.0,
+                });
+            }
+        }
+
+        let signed = match order.side {
+            Side::Buy => order.quantity,
+            Side::Sell => -order.quantity,
+        };
+        let reduces_risk = ctx.position * signed < 0.0 && order.quantity <= ctx.position.abs();
+
+        if let Some(limit) = cfg.max_position_per_symbol {
+            let projected = ctx.position + signed + signed.signum() * ctx.working_same_side;
+            if !reduces_risk && projected.abs() > limit {
+                return Err(RiskViolation::Position { projected, limit });
+            }
+        }
+
+        if let Some(limit) = cfg.max_open_orders {
+            if ctx.open_orders >= limit {
+                return Err(RiskViolation::OpenOrders { open: ctx.open_orders, limit });
+            }
+        }
+
+        if let Some(limit) = self.config.daily_loss_limit {
+            let loss = self.daily_loss(ctx.total_pnl, ctx.now);
+            if loss >= limit && !reduces_risk {
+                return Err(RiskViolation::DailyLoss { loss, limit });
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+    use crate::data_model::{OrderType, TimeInForce};
+
+    fn unlimited() -> RiskConfig {
+        RiskConfig {
+            max_order_quantity: None,
+            max_order_notional: None,
+            price_collar_pct: None,
+            max_position_per_symbol: None,
+            max_open_orders: None,
+            daily_loss_limit: None,
+        }
+    }
+
+    fn at(hour: u32, minute: u32, day: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 3, day, hour, minute, 0).unwrap()
+    }
+
+    fn ctx() -> RiskContext {
+        RiskContext {
+            last_price: Some(100.0),
+            position: 0.0,
+            working_same_side: 0.0,
+            open_orders: 0,
+            total_pnl: 0.0,
+            now: at(12, 0, 1),
+        }
+    }
+
+    fn limit(side: Side, quantity: f64, price: f64) -> Order {
+        Order::new("BTCUSDT".to_string(), side, OrderType::Limit, quantity, Some(price), TimeInForce::GTC)
+    }
+
+    #[test]
+    fn order_quantity_and_notional() {
+        let risk = RiskManager::new(RiskConfig { max_order_quantity: Some(2.0), max_order_notional: Some(150.0), ..unlimited() });
+        assert!(risk.check(&limit(Side::Buy, 1.0, 100.0), &ctx()).is_ok());
+        assert!(matches!(risk.check(&limit(Side::Buy, 3.0, 10.0), &ctx()), Err(RiskViolation::OrderQuantity { .. })));
+        assert!(matches!(risk.check(&limit(Side::Buy, 2.0, 100.0), &ctx()), Err(RiskViolation::OrderNotional { .. })));
+
+        // Market orders are valued at the last price.
+        let market = Order::new("BTCUSDT".to_string(), Side::Sell, OrderType::Market, 2.0, None, TimeInForce::IOC);
+        assert!(matches!(risk.check(&market, &ctx()), Err(RiskViolation::OrderNotional { .. })));
+    }
+
+    #[test]
+    fn price_collar() {
+        let risk = RiskManager::new(RiskConfig { price_collar_pct: Some(0.05), ..unlimited() });
+        assert!(risk.check(&limit(Side::Buy, 1.0, 104.0), &ctx()).is_ok());
+        assert!(matches!(risk.check(&limit(Side::Sell, 1.0, 94.0), &ctx()), Err(RiskViolation::PriceCollar { .. })));
+        assert!(risk.check(&limit(Side::Sell, 1.0, 94.0), &RiskContext { last_price: None, ..ctx() }).is_ok());
+    }
+
+    #[test]
+    fn position_counts_working_orders_but_allows_reductions() {
+        let risk = RiskManager::new(RiskConfig { max_position_per_symbol: Some(5.0), ..unlimited() });
+        let long = RiskContext { position: 3.0, working_same_side: 1.0, ..ctx() };
+        assert!(risk.check(&limit(Side::Buy, 1.0, 100.0), &long).is_ok());
+        assert!(matches!(risk.check(&limit(Side::Buy, 1.5, 100.0), &long), Err(RiskViolation::Position { .. })));
+
+        let over = RiskContext { position: 8.0, ..ctx() };
+        assert!(risk.check(&limit(Side::Sell, 2.0, 100.0), &over).is_ok());
+        assert!(matches!(risk.check(&limit(Side::Sell, 14.0, 100.0), &over), Err(RiskViolation::Position { .. })));
+    }
+
+    #[test]
+    fn open_orders() {
+        let risk = RiskManager::new(RiskConfig { max_open_orders: Some(2), ..unlimited() });
+        assert!(risk.check(&limit(Side::Buy, 1.0, 100.0), &RiskContext { open_orders: 1, ..ctx() }).is_ok());
+        assert!(matches!(
+            risk.check(&limit(Side::Buy, 1.0, 100.0), &RiskContext { open_orders: 2, ..ctx() }),
+            Err(RiskViolation::OpenOrders { open: 2, limit: 2 })
+        ));
+    }
+
+    #[test]
+    fn daily_loss_only_allows_reducing_orders() {
+        let mut risk = RiskManager::new(RiskConfig { daily_loss_limit: Some(100.0), ..unlimited() });
+        risk.roll_day(50.0, at(0, 5, 1));
+        let down = RiskContext { position: 2.0, total_pnl: -50.0, ..ctx() };
+        assert!(matches!(risk.check(&limit(Side::Buy, 1.0, 100.0), &down), Err(RiskViolation::DailyLoss { .. })));
+        assert!(risk.check(&limit(Side::Sell, 1.0, 100.0), &down).is_ok());
+        assert!(risk.check(&limit(Side::Buy, 1.0, 100.0), &RiskContext { total_pnl: -49.0, ..down }).is_ok());
+    }
+
+    #[test]
+    fn daily_loss_resets_at_utc_midnight() {
+        let mut risk = RiskManager::new(RiskConfig { daily_loss_limit: Some(100.0), ..unlimited() });
+        assert!(risk.roll_day(0.0, at(23, 0, 1)).is_some());
+        assert!(risk.roll_day(-80.0, at(23, 59, 1)).is_none());
+        assert_eq!(risk.daily_loss(-150.0, at(23, 59, 1)), 150.0);
+
+        // A stale baseline from yesterday no longer counts.
+        assert_eq!(risk.daily_loss(-150.0, at(0, 0, 2)), 0.0);
+        let start = risk.roll_day(-150.0, at(0, 0, 2)).unwrap();
+        assert_eq!(start, DayStart { day: at(0, 0, 2).date_naive(), pnl: -150.0 });
+        assert_eq!(risk.daily_loss(-180.0, at(0, 1, 2)), 30.0);
+
+        let mut restored = RiskManager::new(RiskConfig { daily_loss_limit: Some(100.0), ..unlimited() });
+        restored.restore_day(start);
+        assert!(restored.roll_day(-180.0, at(0, 1, 2)).is_none());
+        assert_eq!(restored.daily_loss(-180.0, at(0, 1, 2)), 30.0);
+    }
+}
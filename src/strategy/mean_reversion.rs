@@ -1,54 +1,305 @@
-use crate::data_model::{MarketDataUpdate, Order, Side, OrderType, TimeInForce};
-use crate::config::StrategyConfig;
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use crate::data_model::{MarketDataUpdate, Order, Side, OrderType, TimeInForce, QTY_EPSILON};
+use crate::config::{DeviationMode, MeanType, StrategyConfig};
+use crate::oms::{Fill, OmsUpdate};
+use super::Strategy;
 use tracing::{info, debug};
+use uuid::Uuid;
+
+#[derive(Default)]
+struct SymbolState {
+    window: VecDeque<f64>,
+    ema: Option<f64>,
+    position: f64,
+    working_order: Option<Uuid>,
+}
+
+impl SymbolState {
+    fn push_price(&mut self, price: f64, config: &StrategyConfig) {
+        let len = config.window_length.max(1);
+        self.window.push_back(price);
+        while self.window.len() > len {
+            self.window.pop_front();
+        }
+        let alpha = 2.0 / (len as f64 + 1.0);
+        self.ema = Some(match self.ema {
+            Some(prev) => alpha * price + (1.0 - alpha) * prev,
+            None => price,
+        });
+    }
+
+    fn is_warm(&self, config: &StrategyConfig) -> bool {
+        self.window.len() >= config.window_length.max(2)
+    }
 
+    fn mean(&self, config: &StrategyConfig) -> Option<f64> {
+        match config.mean_type {
+            MeanType::Sma if !self.window.is_empty() => {
+                Some(self.window.iter().sum::<f64>() / self.window.len() as f64)
+            }
+            MeanType::Sma => None,
+            MeanType::Ema => self.ema,
+        }
+    }
+
+    fn std_dev(&self, mean: f64) -> f64 {
+        let n = self.window.len();
+        if n < 2 {
+            return 0.0;
+        }
+        let var = self.window.iter().map(|p| (p - mean).powi(2)).sum::<f64>() / (n - 1) as f64;
+        var.sqrt()
+    }
+}
+
+/// Fades deviations of the last price from its rolling mean and exits once
+/// price reverts back through the mean. Orders are IOC limits at the last
+/// price, so one that doesn't fill is canceled at once instead of blocking
+/// later signals.
 pub struct MeanReversionStrategy {
     config: StrategyConfig,
-    
+    symbols: HashMap<String, SymbolState>,
+    own_orders: HashSet<Uuid>,
 }
 
 impl MeanReversionStrategy {
     pub fn new(config: StrategyConfig) -> Self {
         Self {
             config,
-           
+            symbols: HashMap::new(),
+            own_orders: HashSet::new(),
         }
     }
 
-    pub fn process_market_data(&mut self, update: &MarketDataUpdate) -> Option<Order> {
-    
+    fn deviation(&self, state: &SymbolState, price: f64, mean: f64) -> Option<f64> {
+        match self.config.deviation_mode {
+            DeviationMode::Percent if mean > 0.0 => Some((price - mean) / mean),
+            DeviationMode::ZScore => {
+                let sd = state.std_dev(mean);
+                (sd > 0.0).then(|| (price - mean) / sd)
+            }
+            _ => None,
+        }
+    }
 
-        if let Some(last_price) = update.last_price {
-            debug!("Mean Reversion: Processing price {} for {}", last_price, update.symbol);
+    fn signal(&self, state: &SymbolState, price: f64) -> Option<(Side, f64)> {
+        let mean = state.mean(&self.config)?;
 
-        
-            if last_price > 25000.0 && self.config.name == "MeanReversion" && self.config.enabled {
-                info!("Mean Reversion: Buy signal generated for {}", update.symbol);
-                return Some(Order::new(
-                    update.symbol.clone(),
-                    Side::Sell, 
-                    OrderType::Limit,
-                    0.0001, 
-                    Some(last_price), 
-                    TimeInForce::GTC,
-                ));
-            } else if last_price < 19000.0 && self.config.name == "MeanReversion" && self.config.enabled {
-                info!("Mean Reversion: Sell signal generated for {}", update.symbol);
-                return Some(Order::new(
+        if state.position > QTY_EPSILON && price >= mean {
+            return Some((Side::Sell, state.position));
+        }
+        if state.position < -QTY_EPSILON && price <= mean {
+            return Some((Side::Buy, -state.position));
+        }
+
+        let deviation = self.deviation(state, price, mean)?;
+        let threshold = self.config.mean_reversion_threshold;
+        debug!("Mean Reversion: price {} mean {:.4} deviation {:.6}", price, mean, deviation);
+
+        let (side, room) = if deviation <= -threshold {
+            (Side::Buy, self.config.max_position - state.position)
+        } else if deviation >= threshold {
+            (Side::Sell, self.config.max_position + state.position)
+        } else {
+            return None;
+        };
+
+        let quantity = self.config.order_quantity.min(room);
+        (quantity > QTY_EPSILON).then_some((side, quantity))
+    }
+}
+
+impl Strategy for MeanReversionStrategy {
+    fn name(&self) -> &str {
+        &self.config.name
+    }
+
+    fn on_market_data(&mut self, update: &MarketDataUpdate) -> Vec<Order> {
+        let Some(last_price) = update.last_price else {
+            return Vec::new();
+        };
+
+        let mut state = self.symbols.remove(&update.symbol).unwrap_or_default();
+        state.push_price(last_price, &self.config);
+
+        let mut orders = Vec::new();
+        if state.working_order.is_none() && state.is_warm(&self.config) {
+            if let Some((side, quantity)) = self.signal(&state, last_price) {
+                info!("Mean Reversion: {:?} {} {} @ {}", side, quantity, update.symbol, last_price);
+                let order = Order::new(
                     update.symbol.clone(),
-                    Side::Buy, 
+                    side,
                     OrderType::Limit,
-                    0.0001,
+                    quantity,
                     Some(last_price),
-                    TimeInForce::GTC,
-                ));
+                    TimeInForce::IOC,
+                );
+                state.working_order = Some(order.order_id);
+                self.own_orders.insert(order.order_id);
+                orders.push(order);
             }
         }
-        None 
+
+        self.symbols.insert(update.symbol.clone(), state);
+        orders
     }
 
-    pub fn update_config(&mut self, new_config: StrategyConfig) {
-        self.config = new_config;
-        info!("Mean Reversion strategy config updated: {:?}", self.config);
+    fn on_fill(&mut self, fill: &Fill) -> Vec<Order> {
+        if !self.own_orders.contains(&fill.order_id) {
+            return Vec::new();
+        }
+        let state = self.symbols.entry(fill.symbol.clone()).or_default();
+        match fill.side {
+            Side::Buy => state.position += fill.quantity,
+            Side::Sell => state.position -= fill.quantity,
+        }
+        debug!("Mean Reversion: {} position now {}", fill.symbol, state.position);
+        Vec::new()
     }
-}
\ No newline at end of file
+
+    fn on_order_update(&mut self, update: &OmsUpdate) {
+        let OmsUpdate::OrderStateChange { order_id, new_state, .. } = update else {
+            return;
+        };
+        if !new_state.is_terminal() || !self.own_orders.remove(order_id) {
+            return;
+        }
+        for state in self.symbols.values_mut() {
+            if state.working_order == Some(*order_id) {
+                state.working_order = None;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Utc;
+
+    use super::*;
+    use crate::oms::OrderState;
+
+    fn config(threshold: f64, deviation_mode: DeviationMode) -> StrategyConfig {
+        StrategyConfig {
+            enabled: true,
+            name: "MeanReversion".to_string(),
+            mean_reversion_threshold: threshold,
+            window_length: 4,
+            mean_type: MeanType::Sma,
+            deviation_mode,
+            order_quantity: 1.0,
+            max_position: 1.5,
+        }
+    }
+
+    fn trade(price: f64) -> MarketDataUpdate {
+        MarketDataUpdate {
+            symbol: "BTCUSDT".to_string(),
+            timestamp: Utc::now(),
+            bid_price: None,
+            bid_quantity: None,
+            ask_price: None,
+            ask_quantity: None,
+            last_price: Some(price),
+            last_quantity: Some(1.0),
+            aggressor: None,
+        }
+    }
+
+    /// Feeds `prices` and returns the orders sent on the last one.
+    fn feed(strategy: &mut MeanReversionStrategy, prices: &[f64]) -> Vec<Order> {
+        prices.iter().fold(Vec::new(), |_, p| strategy.on_market_data(&trade(*p)))
+    }
+
+    /// Fills `order` completely and reports it done, as the OMS would.
+    fn fill(strategy: &mut MeanReversionStrategy, order: &Order) {
+        strategy.on_fill(&Fill {
+            order_id: order.order_id,
+            symbol: order.symbol.clone(),
+            side: order.side.clone(),
+            quantity: order.quantity,
+            price: order.price.unwrap_or_default(),
+            timestamp: Utc::now(),
+        });
+        strategy.on_order_update(&OmsUpdate::OrderStateChange {
+            order_id: order.order_id,
+            new_state: OrderState::Filled,
+            timestamp: Utc::now(),
+        });
+    }
+
+    #[test]
+    fn sma_and_ema_means() {
+        let mut config = config(0.01, DeviationMode::Percent);
+        config.window_length = 3;
+        let mut state = SymbolState::default();
+        for price in [1.0, 2.0, 3.0, 4.0] {
+            state.push_price(price, &config);
+        }
+        assert_eq!(state.mean(&config), Some(3.0));
+
+        config.mean_type = MeanType::Ema;
+        // Alpha is 2 / (3 + 1) = 0.5: 1, 1.5, 2.25, 3.125.
+        assert_eq!(state.mean(&config), Some(3.125));
+    }
+
+    #[test]
+    fn percent_threshold() {
+        // Window [100, 100, 100, 98]: mean 99.5, deviation -1.51%.
+        let orders = feed(&mut MeanReversionStrategy::new(config(0.015, DeviationMode::Percent)), &[100.0, 100.0, 100.0, 98.0]);
+        assert_eq!(orders.len(), 1);
+        assert_eq!((orders[0].side.clone(), orders[0].price, orders[0].tif.clone()), (Side::Buy, Some(98.0), TimeInForce::IOC));
+
+        let orders = feed(&mut MeanReversionStrategy::new(config(0.02, DeviationMode::Percent)), &[100.0, 100.0, 100.0, 98.0]);
+        assert!(orders.is_empty());
+    }
+
+    #[test]
+    fn zscore_threshold() {
+        // Window [100, 100, 100, 102]: mean 100.5, sample sd 1, z-score 1.5.
+        let orders = feed(&mut MeanReversionStrategy::new(config(1.4, DeviationMode::ZScore)), &[100.0, 100.0, 100.0, 102.0]);
+        assert_eq!(orders.iter().map(|o| o.side.clone()).collect::<Vec<_>>(), vec![Side::Sell]);
+
+        let orders = feed(&mut MeanReversionStrategy::new(config(1.6, DeviationMode::ZScore)), &[100.0, 100.0, 100.0, 102.0]);
+        assert!(orders.is_empty());
+
+        // A flat window has no deviation to measure.
+        let orders = feed(&mut MeanReversionStrategy::new(config(0.0, DeviationMode::ZScore)), &[100.0; 5]);
+        assert!(orders.is_empty());
+    }
+
+    #[test]
+    fn no_new_order_while_one_is_working() {
+        let mut strategy = MeanReversionStrategy::new(config(0.01, DeviationMode::Percent));
+        let first = feed(&mut strategy, &[100.0, 100.0, 100.0, 98.0]);
+        assert_eq!(first.len(), 1);
+        assert!(feed(&mut strategy, &[97.0]).is_empty());
+
+        // Once the IOC order is done, signals are acted on again.
+        strategy.on_order_update(&OmsUpdate::OrderStateChange {
+            order_id: first[0].order_id,
+            new_state: OrderState::Canceled,
+            timestamp: Utc::now(),
+        });
+        assert_eq!(feed(&mut strategy, &[96.0]).len(), 1);
+    }
+
+    #[test]
+    fn size_is_capped_by_max_position_and_exits_at_the_mean() {
+        let mut strategy = MeanReversionStrategy::new(config(0.01, DeviationMode::Percent));
+        let entry = feed(&mut strategy, &[100.0, 100.0, 100.0, 98.0]);
+        assert_eq!(entry[0].quantity, 1.0);
+        fill(&mut strategy, &entry[0]);
+
+        // Only 0.5 left below the 1.5 limit.
+        let add = feed(&mut strategy, &[96.0]);
+        assert_eq!((add[0].side.clone(), add[0].quantity), (Side::Buy, 0.5));
+        fill(&mut strategy, &add[0]);
+        assert!(feed(&mut strategy, &[95.0]).is_empty());
+
+        // Window [98, 96, 95, 99]: mean 97, so 99 is back through it.
+        let exit = feed(&mut strategy, &[99.0]);
+        assert_eq!((exit[0].side.clone(), exit[0].quantity), (Side::Sell, 1.5));
+    }
+}
@@ -1,55 +1,229 @@
 pub mod mean_reversion;
+pub mod registry;
 
 use crate::config::StrategyConfig;
-use crate::data_model::Order; 
+use crate::data_model::{MarketDataUpdate, Order};
+use crate::market_data::candles::CandleUpdate;
+use crate::market_data::{OrderBook, OrderBooks};
+use crate::oms::{Fill, OmsCommand, OmsUpdate};
+use chrono::{DateTime, Utc};
 use tokio::sync::mpsc;
-use tracing::{info, debug}; 
+use tracing::{info, debug, error};
 use anyhow::Result;
 
+pub use registry::StrategyRegistry;
+
 #[derive(Debug, Clone)]
 pub enum StrategyControl {
     Start,
     Stop,
-    UpdateParams(StrategyConfig),
+}
+
+/// A trading strategy driven by the engine. Every callback returns the orders
+/// the strategy wants to send; the engine forwards them to the OMS.
+pub trait Strategy: Send {
+    fn name(&self) -> &str;
+
+    fn on_start(&mut self) -> Vec<Order> {
+        Vec::new()
+    }
+
+    fn on_stop(&mut self) -> Vec<Order> {
+        Vec::new()
+    }
+
+    fn on_market_data(&mut self, update: &MarketDataUpdate) -> Vec<Order>;
+
+    /// Called after a top-of-book change with the synchronized L2 book.
+    fn on_order_book(&mut self, _book: &OrderBook) -> Vec<Order> {
+        Vec::new()
+    }
+
+    /// Called for every in-progress and completed bar; check `closed` to act
+    /// on finished bars only.
+    fn on_candle(&mut self, _update: &CandleUpdate) -> Vec<Order> {
+        Vec::new()
+    }
+
+    fn on_fill(&mut self, _fill: &Fill) -> Vec<Order> {
+        Vec::new()
+    }
+
+    /// Order lifecycle updates other than fills (acks, cancels, rejects).
+    fn on_order_update(&mut self, _update: &OmsUpdate) {}
+
+    fn on_timer(&mut self, _now: DateTime<Utc>) -> Vec<Order> {
+        Vec::new()
+    }
+}
+
+fn forward_orders(orders: Vec<Order>, order_tx: &mpsc::UnboundedSender<OmsCommand>) {
+    for order in orders {
+        info!("Strategy emitted order: {:?} {} {} @ {:?}", order.side, order.quantity, order.symbol, order.price);
+        if let Err(e) = order_tx.send(OmsCommand::New(order)) {
+            error!("Failed to send strategy order to OMS: {}", e);
+        }
+    }
+}
+
+/// Channel ends the strategy engine reads from and writes to.
+pub struct EngineChannels {
+    pub control_rx: mpsc::UnboundedReceiver<StrategyControl>,
+    pub md_rx: mpsc::UnboundedReceiver<MarketDataUpdate>,
+    pub candle_rx: mpsc::UnboundedReceiver<CandleUpdate>,
+    pub oms_rx: mpsc::UnboundedReceiver<OmsUpdate>,
+    pub order_tx: mpsc::UnboundedSender<OmsCommand>,
 }
 
 pub async fn run_strategy_engine(
-    mut config: StrategyConfig,
-    mut control_rx: mpsc::UnboundedReceiver<StrategyControl>,
-    _order_tx: mpsc::UnboundedSender<Order>, 
+    config: StrategyConfig,
+    registry: StrategyRegistry,
+    channels: EngineChannels,
+    books: OrderBooks,
 ) -> Result<()> {
+    let EngineChannels { mut control_rx, mut md_rx, mut candle_rx, mut oms_rx, order_tx } = channels;
     info!("Strategy Engine started. Config: {:?}", config);
 
+    let mut strategy = registry.create(&config)?;
+    info!("Strategy Engine loaded strategy '{}'.", strategy.name());
+
     let mut is_running = config.enabled;
+    if is_running {
+        forward_orders(strategy.on_start(), &order_tx);
+    }
+
+    let mut timer = tokio::time::interval(tokio::time::Duration::from_secs(5));
 
     loop {
         tokio::select! {
             Some(control_msg) = control_rx.recv() => {
                 match control_msg {
                     StrategyControl::Start => {
-                        is_running = true;
                         info!("Strategy Engine received START command.");
+                        if !is_running {
+                            is_running = true;
+                            forward_orders(strategy.on_start(), &order_tx);
+                        }
                     },
                     StrategyControl::Stop => {
-                        is_running = false;
                         info!("Strategy Engine received STOP command.");
-                    },
-                    StrategyControl::UpdateParams(new_config) => {
-                        config = new_config;
-                        info!("Strategy Engine parameters updated: {:?}", config);
+                        if is_running {
+                            is_running = false;
+                            forward_orders(strategy.on_stop(), &order_tx);
+                        }
                     },
                 }
             }
+            Some(update) = md_rx.recv() => {
+                if is_running {
+                    forward_orders(strategy.on_market_data(&update), &order_tx);
+                    if update.bid_price.is_some() || update.ask_price.is_some() {
+                        let orders = books
+                            .read(&update.symbol, |book| book.is_synced().then(|| strategy.on_order_book(book)))
+                            .flatten()
+                            .unwrap_or_default();
+                        forward_orders(orders, &order_tx);
+                    }
+                }
+            }
+            Some(candle) = candle_rx.recv() => {
+                if is_running {
+                    forward_orders(strategy.on_candle(&candle), &order_tx);
+                }
+            }
+            Some(update) = oms_rx.recv() => {
+                match &update {
+                    OmsUpdate::Fill(fill) => {
+                        let orders = strategy.on_fill(fill);
+                        if is_running {
+                            forward_orders(orders, &order_tx);
+                        }
+                    }
+                    _ => strategy.on_order_update(&update),
+                }
+            }
             _ = tokio::signal::ctrl_c() => {
                 info!("Strategy Engine received Ctrl-C, shutting down.");
                 break;
             }
-            _ = tokio::time::sleep(tokio::time::Duration::from_secs(5)) => {
+            now = timer.tick() => {
                 if is_running {
-                    debug!("Strategy heartbeat (replace with actual algo logic)");
+                    debug!("Strategy heartbeat for '{}' at {:?}", strategy.name(), now);
+                    forward_orders(strategy.on_timer(Utc::now()), &order_tx);
                 }
             }
         }
     }
     Ok(())
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::data_model::{OrderType, Side, TimeInForce};
+
+    /// Joins the bid of every quote it sees.
+    struct JoinBid;
+
+    impl Strategy for JoinBid {
+        fn name(&self) -> &str {
+            "JoinBid"
+        }
+
+        fn on_market_data(&mut self, update: &MarketDataUpdate) -> Vec<Order> {
+            update
+                .bid_price
+                .map(|bid| Order::new(update.symbol.clone(), Side::Buy, OrderType::Limit, 1.0, Some(bid), TimeInForce::GTC))
+                .into_iter()
+                .collect()
+        }
+    }
+
+    fn quote(bid: f64) -> MarketDataUpdate {
+        MarketDataUpdate {
+            symbol: "BTCUSDT".to_string(),
+            timestamp: Utc::now(),
+            bid_price: Some(bid),
+            bid_quantity: Some(1.0),
+            ask_price: Some(bid + 1.0),
+            ask_quantity: Some(1.0),
+            last_price: None,
+            last_quantity: None,
+            aggressor: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn engine_passes_quotes_to_the_strategy_and_its_orders_to_the_oms() {
+        let mut registry = StrategyRegistry::new();
+        registry.register("JoinBid", |_| Box::new(JoinBid));
+        let config = StrategyConfig { name: "JoinBid".to_string(), ..crate::config::AppConfig::default().strategy };
+
+        let (control_tx, control_rx) = mpsc::unbounded_channel();
+        let (md_tx, md_rx) = mpsc::unbounded_channel();
+        let (_candle_tx, candle_rx) = mpsc::unbounded_channel();
+        let (_oms_tx, oms_rx) = mpsc::unbounded_channel();
+        let (order_tx, mut order_rx) = mpsc::unbounded_channel();
+        let channels = EngineChannels { control_rx, md_rx, candle_rx, oms_rx, order_tx };
+        let engine = tokio::spawn(run_strategy_engine(config, registry, channels, OrderBooks::new()));
+
+        md_tx.send(quote(100.0)).unwrap();
+        let command = tokio::time::timeout(Duration::from_secs(5), order_rx.recv()).await.unwrap().unwrap();
+        match command {
+            OmsCommand::New(order) => {
+                assert_eq!((order.symbol.as_str(), order.price), ("BTCUSDT", Some(100.0)));
+                assert_eq!(order.side, Side::Buy);
+            }
+            other => panic!("expected a new order, got {:?}", other),
+        }
+
+        control_tx.send(StrategyControl::Stop).unwrap();
+        // The engine's select has no ordering between channels; let Stop land first.
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        md_tx.send(quote(101.0)).unwrap();
+        assert!(tokio::time::timeout(Duration::from_millis(100), order_rx.recv()).await.is_err());
+        engine.abort();
+    }
+}
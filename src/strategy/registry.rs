@@ -0,0 +1,66 @@
+use std::collections::HashMap;
+use anyhow::{anyhow, Result};
+
+use crate::config::StrategyConfig;
+use super::Strategy;
+use super::mean_reversion::MeanReversionStrategy;
+
+pub type StrategyFactory = fn(StrategyConfig) -> Box<dyn Strategy>;
+
+/// Maps `StrategyConfig::name` to a constructor for that strategy.
+#[derive(Clone, Default)]
+pub struct StrategyRegistry {
+    factories: HashMap<String, StrategyFactory>,
+}
+
+impl StrategyRegistry {
+    pub fn new() -> Self {
+        Self { factories: HashMap::new() }
+    }
+
+    pub fn with_builtin() -> Self {
+        let mut registry = Self::new();
+        registry.register("MeanReversion", |config| Box::new(MeanReversionStrategy::new(config)));
+        registry
+    }
+
+    pub fn register(&mut self, name: &str, factory: StrategyFactory) {
+        self.factories.insert(name.to_string(), factory);
+    }
+
+    pub fn names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.factories.keys().cloned().collect();
+        names.sort();
+        names
+    }
+
+    pub fn create(&self, config: &StrategyConfig) -> Result<Box<dyn Strategy>> {
+        let factory = self.factories.get(&config.name).ok_or_else(|| {
+            anyhow!("Unknown strategy '{}'. Available: {:?}", config.name, self.names())
+        })?;
+        Ok(factory(config.clone()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(name: &str) -> StrategyConfig {
+        toml::from_str(&format!("enabled = true\nname = \"{}\"\nmean_reversion_threshold = 0.01\n", name)).unwrap()
+    }
+
+    #[test]
+    fn creates_a_builtin_strategy_by_configured_name() {
+        let registry = StrategyRegistry::with_builtin();
+        assert_eq!(registry.names(), vec!["MeanReversion"]);
+        assert_eq!(registry.create(&config("MeanReversion")).unwrap().name(), "MeanReversion");
+    }
+
+    #[test]
+    fn unknown_name_lists_the_available_strategies() {
+        let err = StrategyRegistry::with_builtin().create(&config("Momentum")).err().unwrap().to_string();
+        assert_eq!(err, "Unknown strategy 'Momentum'. Available: [\"MeanReversion\"]");
+        assert!(StrategyRegistry::new().create(&config("MeanReversion")).is_err());
+    }
+}
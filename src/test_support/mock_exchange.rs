@@ -0,0 +1,155 @@
+use std::net::SocketAddr;
+use std::time::Duration;
+
+use futures_util::{SinkExt, StreamExt};
+use tokio::net::TcpListener;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tokio_tungstenite::tungstenite::handshake::server::{Request, Response};
+use tungstenite::Message;
+
+/// One action of a scripted connection.
+#[derive(Debug, Clone)]
+pub enum Step {
+    Text(String),
+    Ping(Vec<u8>),
+    Sleep(Duration),
+    /// Waits for the client's next text frame before continuing.
+    AwaitText,
+    /// Closing handshake, then the connection ends.
+    Close,
+    /// Drops the TCP connection without a closing handshake.
+    Disconnect,
+}
+
+/// What the server saw from a client, tagged with the connection's index.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ClientEvent {
+    Connected { connection: usize, path: String },
+    Text { connection: usize, text: String },
+    Pong { connection: usize, payload: Vec<u8> },
+    Closed { connection: usize },
+}
+
+/// WebSocket server on a random local port. The n-th accepted connection
+/// plays the n-th script; once a script runs out the connection stays open
+/// until the client leaves. Connections beyond the last script are refused.
+pub struct MockExchange {
+    addr: SocketAddr,
+    events: mpsc::UnboundedReceiver<ClientEvent>,
+    server: JoinHandle<()>,
+}
+
+impl MockExchange {
+    pub async fn start(scripts: Vec<Vec<Step>>) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").await.expect("bind mock exchange");
+        let addr = listener.local_addr().expect("mock exchange address");
+        let (events_tx, events) = mpsc::unbounded_channel();
+
+        let server = tokio::spawn(async move {
+            for (connection, script) in scripts.into_iter().enumerate() {
+                let Ok((stream, _)) = listener.accept().await else {
+                    return;
+                };
+                tokio::spawn(serve(connection, stream, script, events_tx.clone()));
+            }
+        });
+
+        Self { addr, events, server }
+    }
+
+    pub fn url(&self) -> String {
+        format!("ws://{}", self.addr)
+    }
+
+    /// Next client event, failing the test if none arrives in time.
+    pub async fn next_event(&mut self) -> ClientEvent {
+        tokio::time::timeout(Duration::from_secs(5), self.events.recv())
+            .await
+            .expect("timed out waiting for client event")
+            .expect("mock exchange stopped")
+    }
+
+    /// Skips events until one matches `predicate`.
+    pub async fn wait_for(&mut self, predicate: impl Fn(&ClientEvent) -> bool) -> ClientEvent {
+        loop {
+            let event = self.next_event().await;
+            if predicate(&event) {
+                return event;
+            }
+        }
+    }
+}
+
+impl Drop for MockExchange {
+    fn drop(&mut self) {
+        self.server.abort();
+    }
+}
+
+// The handshake callback's error type is tungstenite's, not ours.
+#[allow(clippy::result_large_err)]
+async fn serve(
+    connection: usize,
+    stream: tokio::net::TcpStream,
+    script: Vec<Step>,
+    events_tx: mpsc::UnboundedSender<ClientEvent>,
+) {
+    let mut path = String::new();
+    let capture_path = |request: &Request, response: Response| {
+        path = request.uri().to_string();
+        Ok(response)
+    };
+    let Ok(ws) = tokio_tungstenite::accept_hdr_async(stream, capture_path).await else {
+        return;
+    };
+    let _ = events_tx.send(ClientEvent::Connected { connection, path });
+
+    let (mut write, mut read) = ws.split();
+    // Client text frames, for `AwaitText`.
+    let (text_tx, mut text_rx) = mpsc::unbounded_channel();
+    let reader_events = events_tx.clone();
+    let reader = tokio::spawn(async move {
+        while let Some(Ok(message)) = read.next().await {
+            match message {
+                Message::Text(text) => {
+                    let _ = reader_events.send(ClientEvent::Text { connection, text: text.clone() });
+                    let _ = text_tx.send(text);
+                }
+                Message::Pong(payload) => {
+                    let _ = reader_events.send(ClientEvent::Pong { connection, payload });
+                }
+                Message::Close(_) => break,
+                _ => {}
+            }
+        }
+        let _ = reader_events.send(ClientEvent::Closed { connection });
+    });
+
+    for step in script {
+        let sent = match step {
+            Step::Text(text) => write.send(Message::Text(text)).await,
+            Step::Ping(payload) => write.send(Message::Ping(payload)).await,
+            Step::Sleep(duration) => {
+                tokio::time::sleep(duration).await;
+                Ok(())
+            }
+            Step::AwaitText => {
+                text_rx.recv().await;
+                Ok(())
+            }
+            Step::Close => {
+                let _ = write.send(Message::Close(None)).await;
+                break;
+            }
+            Step::Disconnect => {
+                reader.abort();
+                return;
+            }
+        };
+        if sent.is_err() {
+            break;
+        }
+    }
+    let _ = reader.await;
+}
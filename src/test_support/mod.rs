@@ -0,0 +1,3 @@
+pub mod mock_exchange;
+
+pub use mock_exchange::{ClientEvent, MockExchange, Step};
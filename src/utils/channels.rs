@@ -1 +1,18 @@
-//todo
\ No newline at end of file
+use tokio::sync::mpsc;
+use tracing::debug;
+
+/// Forwards every message from `rx` to each of `subscribers`, dropping
+/// subscribers whose receiving end has gone away. Ends when `rx` closes or
+/// no subscribers remain.
+pub async fn fan_out<T: Clone>(
+    mut rx: mpsc::UnboundedReceiver<T>,
+    mut subscribers: Vec<mpsc::UnboundedSender<T>>,
+) {
+    while let Some(msg) = rx.recv().await {
+        subscribers.retain(|tx| tx.send(msg.clone()).is_ok());
+        if subscribers.is_empty() {
+            debug!("All fan-out subscribers dropped, stopping.");
+            break;
+        }
+    }
+}
@@ -1,4 +1,5 @@
 pub mod channels;
 pub mod logger; 
 
-pub use logger::init_logging;
\ No newline at end of file
+pub use channels::fan_out;
+pub use logger::init_logging;